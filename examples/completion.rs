@@ -1,14 +1,11 @@
-use chatgpt_client::{ChatInput, Client, Message, Result, Role};
+use chatgpt_client::{ChatInput, Client, Result, Role};
 use std::env;
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let api_key = env::var("OPENAI_API_KEY").expect("OPENAI_API_KEY must be set");
     let client = Client::new(api_key)?;
-    let input = ChatInput {
-        messages: &[Message::system("Hello, World!")],
-        ..Default::default()
-    };
+    let input = ChatInput::builder().system("Hello, World!").build();
     let response = client.completion(&input).await?;
     let assistant_message = &response.choices[0].message;
 
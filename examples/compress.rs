@@ -1,4 +1,5 @@
-use chatgpt_client::{ChatInput, Client, Message, Result};
+use chatgpt_client::segmenter::Lines;
+use chatgpt_client::{ChatInput, Client, Result};
 use futures::stream::StreamExt;
 use std::env;
 
@@ -6,13 +7,12 @@ use std::env;
 async fn main() -> Result<()> {
     let api_key = env::var("OPENAI_API_KEY").expect("OPENAI_API_KEY must be set");
     let client = Client::new(api_key)?;
-    let input = ChatInput {
-        messages: &[Message::system("Introduce yourself in 3 points")],
-        stream: Some(true),
-        ..Default::default()
-    };
+    let input = ChatInput::builder()
+        .system("Introduce yourself in 3 points")
+        .stream(true)
+        .build();
 
-    let stream = client.compress(&input, "\n").await?;
+    let stream = client.compress(&input, Lines::new()).await?;
     stream
         .for_each(|result| {
             match result {
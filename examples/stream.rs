@@ -1,4 +1,4 @@
-use chatgpt_client::{ChatInput, Client, Message, Result};
+use chatgpt_client::{ChatInput, Client, Result};
 use futures::stream::StreamExt;
 use std::env;
 
@@ -6,11 +6,11 @@ use std::env;
 async fn main() -> Result<()> {
     let api_key = env::var("OPENAI_API_KEY").expect("OPENAI_API_KEY must be set");
     let client = Client::new(api_key)?;
-    let input = ChatInput {
-        messages: &[Message::system("Hello, World!")],
-        stream: Some(true),
-        ..Default::default()
-    };
+    let input = ChatInput::builder()
+        .system("Hello, World!")
+        .stream(true)
+        .include_usage(true)
+        .build();
 
     let stream = client.stream(&input).await?;
     stream
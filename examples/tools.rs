@@ -0,0 +1,32 @@
+use chatgpt_client::{ChatInput, Client, Result, ToolRegistry};
+use serde::Deserialize;
+use serde_json::json;
+use std::env;
+
+#[derive(Deserialize)]
+struct WeatherArgs {
+    city: String,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let api_key = env::var("OPENAI_API_KEY").expect("OPENAI_API_KEY must be set");
+    let client = Client::new(api_key)?;
+    let registry = ToolRegistry::new().register(
+        "get_weather",
+        "Get the current weather in a city",
+        json!({
+            "type": "object",
+            "properties": { "city": { "type": "string" } },
+            "required": ["city"],
+        }),
+        |WeatherArgs { city }: WeatherArgs| async move { Ok(format!("It is sunny in {city}.")) },
+    );
+    let input = ChatInput::builder()
+        .user("What is the weather like in Paris?")
+        .build();
+
+    let run = client.run_with_tools(&input, &registry).await?;
+    println!("Response: {}", run.response.choices[0].message.content);
+    Ok(())
+}
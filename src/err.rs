@@ -1,4 +1,6 @@
 use reqwest::{header::HeaderMap, StatusCode};
+use serde::Deserialize;
+use std::fmt::{Display, Formatter};
 use thiserror::Error;
 
 /// Defines the possible errors that can occur when interacting with the ChatGPT API.
@@ -17,20 +19,302 @@ pub enum Error {
     #[error("Reqwest client error occurred: {0}")]
     ReqwestClientError(#[from] reqwest::Error),
 
-    /// Represents a failed request error.
-    #[error("Request failed with status code: {0}, headers: {1:?}, body: {2}")]
-    RequestFailed(StatusCode, HeaderMap, String),
+    /// Represents a failed request error, carrying the error returned by the API.
+    #[error("Request failed with {0}")]
+    RequestFailed(Box<ApiError>),
+
+    /// Represents the error of the final attempt after retrying a request.
+    #[error("Request failed after {attempts} attempts: {source}")]
+    RetriesExhausted {
+        /// The number of attempts that were made.
+        attempts: u32,
+        /// The error returned by the final attempt.
+        source: Box<Error>,
+    },
+
+    /// Represents a logit bias outside of the range accepted by the API.
+    #[error("Logit bias {bias} for token {token} is outside the range [-100, 100]")]
+    InvalidLogitBias {
+        /// The id of the token.
+        token: u32,
+        /// The out-of-range bias.
+        bias: i16,
+    },
+
+    /// Represents a tool call naming a tool that is not registered.
+    #[error("Unknown tool: {0}")]
+    UnknownTool(String),
+
+    /// Represents a tool function that failed to run to completion.
+    #[error("Tool execution failed: {0}")]
+    ToolError(String),
+
+    /// Represents a tool run that did not reach a final answer within the iteration limit.
+    #[error("No final answer after {0} iterations of tool calls")]
+    ToolIterationsExceeded(usize),
+
+    /// Represents a request whose messages that cannot be left out do not fit the context window.
+    #[error("The prompt needs {required} tokens, but only {available} are available")]
+    ContextLengthExceeded {
+        /// The number of tokens of the messages that cannot be left out.
+        required: usize,
+        /// The number of tokens available to the prompt.
+        available: usize,
+    },
+
+    /// Represents model output that could not be deserialized into the expected type.
+    #[error("Model output is not valid JSON for the expected type: {source}")]
+    InvalidJsonOutput {
+        /// The content returned by the model.
+        content: String,
+        /// The error raised while deserializing the content.
+        source: serde_json::Error,
+    },
 
     /// Represents an error that occurred while processing the response.
     #[error("Error occurred while processing the response: {0}")]
     ResponseError(String),
 
+    /// Represents a streamed event that is neither a chunk nor an error returned by the API (when
+    /// using the `stream` feature).
+    #[cfg(feature = "stream")]
+    #[error("Unexpected response chunk: {source}")]
+    UnexpectedChunk {
+        /// The data of the event.
+        data: String,
+        /// The error raised while deserializing the chunk.
+        source: serde_json::Error,
+    },
+
     /// Represents an event stream error (when using the `stream` feature).
     #[cfg(feature = "stream")]
     #[error("Event stream error occurred: {0}")]
     EventStreamError(#[from] eventsource_stream::EventStreamError<reqwest::Error>),
 
+    /// Represents a stream that ended before every choice received a finish reason (when using
+    /// the `stream` feature).
+    #[cfg(feature = "stream")]
+    #[error("Stream ended before choices {unfinished:?} received a finish reason")]
+    IncompleteStream {
+        /// The indices of the choices without a finish reason, empty if no choice was received.
+        unfinished: Vec<usize>,
+    },
+
+    /// Represents a stream that took longer than one of its `StreamTimeouts` (when using the
+    /// `stream` feature).
+    #[cfg(feature = "stream")]
+    #[error("Stream {kind} timeout of {duration:?} elapsed")]
+    StreamTimeout {
+        /// The timeout that elapsed.
+        kind: StreamTimeoutKind,
+        /// The duration of the timeout.
+        duration: std::time::Duration,
+        /// The content received so far, indexed by choice.
+        partial: Vec<String>,
+    },
+
+    /// Represents an error that occurred while loading a tokenizer vocabulary (when using the
+    /// `tokenizer` feature).
+    #[cfg(feature = "tokenizer")]
+    #[error("Tokenizer error occurred: {0}")]
+    TokenizerError(String),
+
+    /// Represents an embedding whose number of dimensions differs from the vectors of a
+    /// `VectorIndex`.
+    #[error("Expected a vector of {expected} dimensions, received {actual}")]
+    DimensionMismatch {
+        /// The dimensions of the vectors of the index.
+        expected: usize,
+        /// The dimensions of the rejected vector.
+        actual: usize,
+    },
+
+    /// Represents a `VectorIndex` whose HNSW graph does not match its documents, such as a
+    /// truncated or edited file.
+    #[error("Invalid vector index: {0}")]
+    InvalidVectorIndex(String),
+
+    /// Represents a conversation identifier that cannot be used by a storage backend.
+    #[error("Invalid conversation identifier: {0}")]
+    InvalidConversationId(String),
+
+    /// Represents a SQLite error (when using the `sqlite` feature).
+    #[cfg(feature = "sqlite")]
+    #[error("SQLite error occurred: {0}")]
+    SqliteError(#[from] rusqlite::Error),
+
+    /// Represents an error that occurred while reading or writing a file.
+    #[error("I/O error occurred: {0}")]
+    IoError(#[from] std::io::Error),
+
     /// Represents an error that occurred while processing JSON data.
     #[error("Error occurred while processing JSON data: {0}")]
     SerdeJsonError(#[from] serde_json::Error),
 }
+
+impl Error {
+    /// Returns the `ApiError` behind this error, if the request was rejected by the API.
+    ///
+    /// Errors wrapped in `Error::RetriesExhausted` are looked through.
+    ///
+    /// # Returns
+    ///
+    /// An `Option` containing a reference to the `ApiError`.
+    pub fn api_error(&self) -> Option<&ApiError> {
+        match self {
+            Error::RequestFailed(api_error) => Some(api_error),
+            Error::RetriesExhausted { source, .. } => source.api_error(),
+            _ => None,
+        }
+    }
+}
+
+/// Classifies the errors returned by the API.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ApiErrorKind {
+    /// Too many requests were sent in a given amount of time.
+    RateLimit,
+    /// The API key is missing, invalid or lacks the required permissions.
+    Authentication,
+    /// The account has run out of credits or reached its billing limit.
+    InsufficientQuota,
+    /// The messages and `max_tokens` exceed the context window of the model.
+    ContextLengthExceeded,
+    /// The request was malformed or contained invalid parameters.
+    InvalidRequest,
+    /// The server failed to process a valid request.
+    Server,
+    /// Any other error.
+    Other,
+}
+
+impl ApiErrorKind {
+    /// Classifies an error from its status code and the `type` and `code` fields of its body.
+    fn classify(status: StatusCode, r#type: Option<&str>, code: Option<&str>) -> Self {
+        match (code, r#type) {
+            (Some("context_length_exceeded"), _) => return ApiErrorKind::ContextLengthExceeded,
+            (Some("insufficient_quota"), _) | (_, Some("insufficient_quota")) => {
+                return ApiErrorKind::InsufficientQuota
+            }
+            _ => {}
+        }
+        match status {
+            StatusCode::TOO_MANY_REQUESTS => ApiErrorKind::RateLimit,
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => ApiErrorKind::Authentication,
+            StatusCode::BAD_REQUEST | StatusCode::NOT_FOUND | StatusCode::UNPROCESSABLE_ENTITY => {
+                ApiErrorKind::InvalidRequest
+            }
+            status if status.is_server_error() => ApiErrorKind::Server,
+            _ if r#type == Some("invalid_request_error") => ApiErrorKind::InvalidRequest,
+            _ => ApiErrorKind::Other,
+        }
+    }
+}
+
+/// Identifies the timeout of a stream that elapsed (when using the `stream` feature).
+#[cfg(feature = "stream")]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum StreamTimeoutKind {
+    /// The whole stream took too long.
+    Total,
+    /// Too much time passed without receiving a token.
+    Idle,
+}
+
+#[cfg(feature = "stream")]
+impl Display for StreamTimeoutKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StreamTimeoutKind::Total => write!(f, "total"),
+            StreamTimeoutKind::Idle => write!(f, "idle"),
+        }
+    }
+}
+
+/// Represents an error response returned by the API.
+///
+/// The fields are parsed from the `{"error": {...}}` envelope of the response body. If the body
+/// is not in that format, `message` contains the raw body text.
+#[derive(Debug, Clone)]
+pub struct ApiError {
+    /// The HTTP status code of the response.
+    pub status: StatusCode,
+    /// The headers of the response.
+    pub headers: HeaderMap,
+    /// The classification of the error.
+    pub kind: ApiErrorKind,
+    /// The human-readable error message.
+    pub message: String,
+    /// The error type, such as "invalid_request_error".
+    pub r#type: Option<String>,
+    /// The request parameter the error relates to, if any.
+    pub param: Option<String>,
+    /// The error code, such as "context_length_exceeded".
+    pub code: Option<String>,
+}
+
+/// The `{"error": {...}}` envelope of an API error response.
+#[derive(Deserialize)]
+struct ErrorEnvelope {
+    error: ErrorBody,
+}
+
+#[derive(Deserialize)]
+struct ErrorBody {
+    message: Option<String>,
+    r#type: Option<String>,
+    param: Option<String>,
+    code: Option<serde_json::Value>,
+}
+
+impl ApiError {
+    /// Creates an `ApiError` from the parts of a failed response.
+    ///
+    /// # Arguments
+    ///
+    /// * `status` - The HTTP status code of the response.
+    /// * `headers` - The headers of the response.
+    /// * `body` - The raw body text of the response.
+    ///
+    /// # Returns
+    ///
+    /// A new `ApiError` instance.
+    pub fn new(status: StatusCode, headers: HeaderMap, body: String) -> Self {
+        let (message, r#type, param, code) = match serde_json::from_str::<ErrorEnvelope>(&body) {
+            Ok(ErrorEnvelope { error }) => {
+                let code = error.code.and_then(|code| match code {
+                    serde_json::Value::String(code) => Some(code),
+                    serde_json::Value::Null => None,
+                    code => Some(code.to_string()),
+                });
+                (
+                    error.message.unwrap_or(body),
+                    error.r#type,
+                    error.param,
+                    code,
+                )
+            }
+            Err(_) => (body, None, None, None),
+        };
+        let kind = ApiErrorKind::classify(status, r#type.as_deref(), code.as_deref());
+        Self {
+            status,
+            headers,
+            kind,
+            message,
+            r#type,
+            param,
+            code,
+        }
+    }
+}
+
+impl Display for ApiError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "status code {}: {}", self.status, self.message)?;
+        if let Some(code) = &self.code {
+            write!(f, " ({code})")?;
+        }
+        Ok(())
+    }
+}
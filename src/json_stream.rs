@@ -0,0 +1,596 @@
+//! Incremental parsing of JSON output as it is streamed (when using the `stream` feature).
+//!
+//! `JsonParser` consumes the content of a response fragment by fragment and reports every value
+//! as soon as it is complete: the fields of objects, the elements of arrays, and finally the whole
+//! document. Values are located by their JSON Pointer (RFC 6901), such as `/items/0/name`, which
+//! can be passed to `serde_json::Value::pointer`.
+//!
+//! `Client::stream_json` and `Client::stream_json_items` apply the parser to a streamed response,
+//! the latter deserializing the elements of an array into a user type as they complete.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use chatgpt_client::{ChatInput, Client};
+//! use futures::StreamExt;
+//! use serde::Deserialize;
+//!
+//! #[derive(Debug, Deserialize)]
+//! struct City {
+//!     name: String,
+//!     country: String,
+//! }
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let client = Client::new(std::env::var("OPENAI_API_KEY").unwrap()).unwrap();
+//!     let input = ChatInput::builder()
+//!         .system("Reply with a JSON object with a `cities` array of `name` and `country`.")
+//!         .user("List the 10 largest cities in Europe.")
+//!         .stream(true)
+//!         .build();
+//!
+//!     let cities = client.stream_json_items::<City>(&input, "/cities").await.unwrap();
+//!     let mut cities = std::pin::pin!(cities);
+//!     while let Some(city) = cities.next().await {
+//!         println!("{:?}", city.unwrap());
+//!     }
+//! }
+//! ```
+
+use crate::types::StreamItem;
+use crate::{Error, Result};
+use futures::stream::{unfold, Stream, StreamExt};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::collections::VecDeque;
+
+/// Represents a value completed while parsing streamed JSON.
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonEvent {
+    /// A field of an object is complete.
+    Field {
+        /// The JSON Pointer of the field.
+        pointer: String,
+        /// The value of the field.
+        value: Value,
+    },
+    /// An element of an array is complete.
+    Element {
+        /// The JSON Pointer of the element.
+        pointer: String,
+        /// The index of the element in its array.
+        index: usize,
+        /// The value of the element.
+        value: Value,
+    },
+    /// The whole document is complete. This is always the last event.
+    Complete(Value),
+}
+
+impl JsonEvent {
+    /// Returns the JSON Pointer of the completed value, which is empty for the whole document.
+    pub fn pointer(&self) -> &str {
+        match self {
+            JsonEvent::Field { pointer, .. } | JsonEvent::Element { pointer, .. } => pointer,
+            JsonEvent::Complete(_) => "",
+        }
+    }
+
+    /// Returns the completed value.
+    pub fn value(&self) -> &Value {
+        match self {
+            JsonEvent::Field { value, .. }
+            | JsonEvent::Element { value, .. }
+            | JsonEvent::Complete(value) => value,
+        }
+    }
+}
+
+/// An object or array being parsed.
+#[derive(Debug)]
+struct Frame {
+    array: bool,
+    pointer: String,
+    key: Option<String>,
+    key_start: Option<usize>,
+    expect_key: bool,
+    index: usize,
+    value_start: Option<usize>,
+}
+
+impl Frame {
+    fn new(array: bool, pointer: String) -> Self {
+        Self {
+            array,
+            pointer,
+            key: None,
+            key_start: None,
+            expect_key: !array,
+            index: 0,
+            value_start: None,
+        }
+    }
+
+    /// Returns the JSON Pointer of the value being parsed in this frame.
+    fn child_pointer(&self) -> String {
+        match (&self.key, self.array) {
+            (_, true) => format!("{}/{}", self.pointer, self.index),
+            (Some(key), false) => format!(
+                "{}/{}",
+                self.pointer,
+                key.replace('~', "~0").replace('/', "~1")
+            ),
+            (None, false) => self.pointer.clone(),
+        }
+    }
+}
+
+/// An incremental JSON parser reporting values as they complete.
+///
+/// Any text before the first `{` or `[`, such as an opening Markdown code fence, and after the
+/// end of the document is ignored. Documents whose root is not an object or an array are only
+/// reported by `finish`.
+#[derive(Debug, Default)]
+pub struct JsonParser {
+    buffer: String,
+    position: usize,
+    root_start: Option<usize>,
+    stack: Vec<Frame>,
+    in_string: bool,
+    escape: bool,
+    scalar_start: Option<usize>,
+    complete: bool,
+}
+
+impl JsonParser {
+    /// Creates an empty `JsonParser`.
+    ///
+    /// # Returns
+    ///
+    /// A new `JsonParser` instance.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the content received so far.
+    pub fn content(&self) -> &str {
+        &self.buffer
+    }
+
+    /// Returns `true` once the whole document has been parsed.
+    pub fn is_complete(&self) -> bool {
+        self.complete
+    }
+
+    /// Adds a content fragment and returns the values it completes.
+    ///
+    /// # Arguments
+    ///
+    /// * `content` - The next content fragment.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the completed values, innermost first, or
+    /// `Error::InvalidJsonOutput` if the content is not valid JSON.
+    pub fn push(&mut self, content: &str) -> Result<Vec<JsonEvent>> {
+        self.buffer.push_str(content);
+        let mut events = Vec::new();
+
+        // Structural characters are ASCII, which never occurs inside multi-byte characters.
+        while self.position < self.buffer.len() && !self.complete {
+            let position = self.position;
+            let byte = self.buffer.as_bytes()[position];
+            self.position += 1;
+
+            if self.in_string {
+                if self.escape {
+                    self.escape = false;
+                } else if byte == b'\\' {
+                    self.escape = true;
+                } else if byte == b'"' {
+                    self.in_string = false;
+                    self.end_string(position + 1, &mut events)?;
+                }
+                continue;
+            }
+            if let Some(start) = self.scalar_start {
+                if !matches!(byte, b',' | b'}' | b']') && !byte.is_ascii_whitespace() {
+                    continue;
+                }
+                self.scalar_start = None;
+                self.end_value(start, position, &mut events)?;
+            }
+            if self.root_start.is_none() {
+                if matches!(byte, b'{' | b'[') {
+                    self.root_start = Some(position);
+                    self.stack.push(Frame::new(byte == b'[', String::new()));
+                }
+                continue;
+            }
+
+            match byte {
+                b'{' | b'[' => {
+                    let frame = self.value_frame(position)?;
+                    frame.value_start = Some(position);
+                    let pointer = frame.child_pointer();
+                    self.stack.push(Frame::new(byte == b'[', pointer));
+                }
+                b'}' | b']' => {
+                    self.stack.pop();
+                    match self.stack.last() {
+                        Some(frame) => {
+                            let start = frame.value_start.unwrap_or(position);
+                            self.end_value(start, position + 1, &mut events)?;
+                        }
+                        None => {
+                            let start = self.root_start.unwrap_or_default();
+                            events.push(JsonEvent::Complete(
+                                self.parse(&self.buffer[start..=position])?,
+                            ));
+                            self.complete = true;
+                        }
+                    }
+                }
+                b'"' => {
+                    self.in_string = true;
+                    let frame = self.frame()?;
+                    if frame.expect_key {
+                        frame.key_start = Some(position);
+                    } else {
+                        frame.value_start = Some(position);
+                    }
+                }
+                b',' => {
+                    let frame = self.frame()?;
+                    frame.expect_key = !frame.array;
+                }
+                b':' => self.frame()?.expect_key = false,
+                byte if byte.is_ascii_whitespace() => {}
+                _ => {
+                    self.value_frame(position)?.value_start = Some(position);
+                    self.scalar_start = Some(position);
+                }
+            }
+        }
+        Ok(events)
+    }
+
+    /// Ends the document once the content is complete.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the whole document, or `Error::InvalidJsonOutput` if the content is
+    /// not a complete JSON document.
+    pub fn finish(self) -> Result<Value> {
+        let start = self.root_start.unwrap_or_default();
+        let end = if self.complete {
+            self.position
+        } else {
+            self.buffer.len()
+        };
+        let document = &self.buffer[start..end];
+        // A root that is not an object or an array only ends with the content.
+        let document = match self.root_start {
+            Some(_) => document,
+            None => crate::types::response_format::strip_code_fences(document),
+        };
+        self.parse(document)
+    }
+
+    /// Returns the innermost object or array being parsed.
+    fn frame(&mut self) -> Result<&mut Frame> {
+        let Self { stack, buffer, .. } = self;
+        match stack.last_mut() {
+            Some(frame) => Ok(frame),
+            None => Err(invalid(buffer, buffer)),
+        }
+    }
+
+    /// Returns the innermost object or array, which must expect a value rather than a key, as
+    /// keys can only be strings.
+    fn value_frame(&mut self, position: usize) -> Result<&mut Frame> {
+        let Self {
+            stack,
+            buffer,
+            root_start,
+            ..
+        } = self;
+        match stack.last_mut() {
+            Some(frame) if frame.array || !frame.expect_key => Ok(frame),
+            _ => {
+                let start = root_start.unwrap_or_default();
+                Err(invalid(buffer, &buffer[start..=position]))
+            }
+        }
+    }
+
+    /// Handles the end of a string, which is either a key or a value.
+    fn end_string(&mut self, end: usize, events: &mut Vec<JsonEvent>) -> Result<()> {
+        let frame = self.frame()?;
+        let (key_start, value_start) = (frame.key_start.take(), frame.value_start);
+        match key_start {
+            Some(start) => {
+                let key = &self.buffer[start..end];
+                let key = serde_json::from_str(key).map_err(|_| invalid(&self.buffer, key))?;
+                let frame = self.frame()?;
+                frame.key = Some(key);
+                frame.expect_key = false;
+                Ok(())
+            }
+            None => self.end_value(value_start.unwrap_or_default(), end, events),
+        }
+    }
+
+    /// Reports the value of the innermost object or array that ends at `end`.
+    fn end_value(&mut self, start: usize, end: usize, events: &mut Vec<JsonEvent>) -> Result<()> {
+        let value = self.parse(&self.buffer[start..end])?;
+        let frame = self.frame()?;
+        frame.value_start = None;
+        let pointer = frame.child_pointer();
+        if frame.array {
+            events.push(JsonEvent::Element {
+                pointer,
+                index: frame.index,
+                value,
+            });
+            frame.index += 1;
+        } else {
+            events.push(JsonEvent::Field { pointer, value });
+        }
+        Ok(())
+    }
+
+    /// Parses a complete value.
+    fn parse(&self, json: &str) -> Result<Value> {
+        serde_json::from_str(json).map_err(|source| Error::InvalidJsonOutput {
+            content: self.buffer.clone(),
+            source,
+        })
+    }
+}
+
+/// Builds the error reporting that a fragment of the content is not valid JSON.
+fn invalid(content: &str, json: &str) -> Error {
+    let source = serde_json::from_str::<Value>(json)
+        .err()
+        .unwrap_or_else(|| serde::de::Error::custom("unexpected JSON value"));
+    Error::InvalidJsonOutput {
+        content: content.to_string(),
+        source,
+    }
+}
+
+/// Parses the content of a stream of `StreamItem`s as JSON, reporting values as they complete.
+///
+/// The content of every `StreamItem::Content` is parsed as one document, so the items of
+/// responses with several choices must be split with `demultiplex` first. Errors of the stream
+/// are passed through.
+///
+/// # Arguments
+///
+/// * `stream` - A stream of `StreamItem`s, such as the one returned by `Client::stream`.
+///
+/// # Returns
+///
+/// A stream of `JsonEvent`s, ending with `JsonEvent::Complete`, or `Error::InvalidJsonOutput` if
+/// the content is not a complete JSON document.
+pub fn json_events<S>(stream: S) -> impl Stream<Item = Result<JsonEvent>>
+where
+    S: Stream<Item = Result<StreamItem>>,
+{
+    let stream = Box::pin(stream);
+    unfold(
+        (stream, Some(JsonParser::new()), VecDeque::new()),
+        |(mut stream, mut parser, mut pending)| async move {
+            loop {
+                if let Some(event) = pending.pop_front() {
+                    return Some((event, (stream, parser, pending)));
+                }
+                let parser_ref = parser.as_mut()?;
+                match stream.next().await {
+                    Some(Ok(StreamItem::Content { content, .. })) => {
+                        match parser_ref.push(&content) {
+                            Ok(events) => pending.extend(events.into_iter().map(Ok)),
+                            Err(err) => return Some((Err(err), (stream, None, pending))),
+                        }
+                    }
+                    Some(Ok(_)) => {}
+                    Some(Err(err)) => pending.push_back(Err(err)),
+                    None => {
+                        let parser = parser.take()?;
+                        if !parser.is_complete() {
+                            let event = parser.finish().map(JsonEvent::Complete);
+                            return Some((event, (stream, None, pending)));
+                        }
+                    }
+                }
+            }
+        },
+    )
+}
+
+/// Deserializes the elements of an array into `T` as they complete in a stream of `JsonEvent`s.
+///
+/// # Arguments
+///
+/// * `events` - A stream of `JsonEvent`s, such as the one returned by `json_events`.
+/// * `pointer` - The JSON Pointer of the array, such as `/items`, or `""` for a root array.
+///
+/// # Returns
+///
+/// A stream of the deserialized elements, or `Error::InvalidJsonOutput` for an element that is
+/// not a valid `T`. Errors of the stream are passed through.
+pub fn json_items<T, S>(events: S, pointer: &str) -> impl Stream<Item = Result<T>>
+where
+    T: DeserializeOwned,
+    S: Stream<Item = Result<JsonEvent>>,
+{
+    let prefix = format!("{pointer}/");
+    events.filter_map(move |event| {
+        let item = match event {
+            Ok(JsonEvent::Element { pointer, value, .. })
+                if pointer
+                    .strip_prefix(&prefix)
+                    .is_some_and(|index| !index.contains('/')) =>
+            {
+                Some(serde_json::from_value(value.clone()).map_err(|source| {
+                    Error::InvalidJsonOutput {
+                        content: value.to_string(),
+                        source,
+                    }
+                }))
+            }
+            Ok(_) => None,
+            Err(err) => Some(Err(err)),
+        };
+        async move { item }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Feeds the content to a parser in fragments of `size` bytes, split at character boundaries.
+    fn parse_in(content: &str, size: usize) -> Result<Vec<JsonEvent>> {
+        let mut parser = JsonParser::new();
+        let mut events = Vec::new();
+        let mut rest = content;
+        while !rest.is_empty() {
+            let mut end = size.min(rest.len());
+            while !rest.is_char_boundary(end) {
+                end += 1;
+            }
+            events.extend(parser.push(&rest[..end])?);
+            rest = &rest[end..];
+        }
+        if !parser.is_complete() {
+            events.push(JsonEvent::Complete(parser.finish()?));
+        }
+        Ok(events)
+    }
+
+    /// Parses the content in fragments of every size and checks they all agree.
+    fn parse(content: &str) -> Result<Vec<JsonEvent>> {
+        let whole = parse_in(content, content.len().max(1));
+        for size in 1..content.len() {
+            match (&whole, parse_in(content, size)) {
+                (Ok(whole), Ok(events)) => assert_eq!(whole, &events, "fragments of {size}"),
+                (Err(_), Err(_)) => {}
+                (whole, events) => panic!("fragments of {size}: {whole:?} != {events:?}"),
+            }
+        }
+        whole
+    }
+
+    fn field(pointer: &str, value: Value) -> JsonEvent {
+        JsonEvent::Field {
+            pointer: pointer.to_string(),
+            value,
+        }
+    }
+
+    fn element(pointer: &str, index: usize, value: Value) -> JsonEvent {
+        JsonEvent::Element {
+            pointer: pointer.to_string(),
+            index,
+            value,
+        }
+    }
+
+    #[test]
+    fn reports_nested_values_innermost_first() {
+        let content =
+            r#"{"cities": [{"name": "Paris", "tags": ["fr"]}, {"name": "Rome"}], "n": 2}"#;
+        let document: Value = serde_json::from_str(content).unwrap();
+        assert_eq!(
+            parse(content).unwrap(),
+            [
+                field("/cities/0/name", json!("Paris")),
+                element("/cities/0/tags/0", 0, json!("fr")),
+                field("/cities/0/tags", json!(["fr"])),
+                element("/cities/0", 0, document["cities"][0].clone()),
+                field("/cities/1/name", json!("Rome")),
+                element("/cities/1", 1, json!({"name": "Rome"})),
+                field("/cities", document["cities"].clone()),
+                field("/n", json!(2)),
+                JsonEvent::Complete(document),
+            ]
+        );
+    }
+
+    #[test]
+    fn decodes_escapes_and_surrogate_pairs() {
+        let content = r#"{"a/b~c": "quote \" backslash \\ brace } \u00e9 \ud83d\ude00 ü"}"#;
+        let events = parse(content).unwrap();
+        assert_eq!(
+            events[0],
+            field("/a~1b~0c", json!("quote \" backslash \\ brace } é 😀 ü"))
+        );
+    }
+
+    #[test]
+    fn parses_numbers_split_across_fragments() {
+        let events = parse("[12345, -0.5e-3, true, null]").unwrap();
+        assert_eq!(
+            events[..4],
+            [
+                element("/0", 0, json!(12345)),
+                element("/1", 1, json!(-0.5e-3)),
+                element("/2", 2, json!(true)),
+                element("/3", 3, Value::Null),
+            ]
+        );
+        let mut parser = JsonParser::new();
+        assert!(parser.push("[12").unwrap().is_empty());
+        assert_eq!(
+            parser.push("34]").unwrap()[0],
+            element("/0", 0, json!(1234))
+        );
+    }
+
+    #[test]
+    fn ignores_text_around_the_document() {
+        let events = parse("```json\n{\"a\": [1]}\n```").unwrap();
+        assert_eq!(events.last(), Some(&JsonEvent::Complete(json!({"a": [1]}))));
+
+        let mut parser = JsonParser::new();
+        parser.push("[1] trailing").unwrap();
+        assert!(parser.is_complete());
+        assert_eq!(parser.finish().unwrap(), json!([1]));
+    }
+
+    #[test]
+    fn rejects_keys_that_are_not_strings() {
+        for content in ["{1: 2", "{\"a\": 1, true: 2", "{[1]: 2", "{{"] {
+            assert!(JsonParser::new().push(content).is_err(), "{content}");
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_json() {
+        for content in [
+            r#"{1: 2}"#,
+            r#"{"a": 1, [2]: 3}"#,
+            r#"{{}}"#,
+            r#"{"a": tru}"#,
+            r#"[1, 2"#,
+        ] {
+            assert!(
+                matches!(parse(content), Err(Error::InvalidJsonOutput { .. })),
+                "{content}"
+            );
+        }
+    }
+
+    #[test]
+    fn reports_scalar_roots_on_finish() {
+        assert_eq!(parse("42").unwrap(), [JsonEvent::Complete(json!(42))]);
+        assert_eq!(
+            parse("\"text\"").unwrap(),
+            [JsonEvent::Complete(json!("text"))]
+        );
+    }
+}
@@ -3,32 +3,36 @@
 //! This library provides an easy-to-use interface for generating text completions using
 //! the ChatGPT API. The main components are:
 //! - `Client`: The main API client for interacting with the ChatGPT API.
-//! - `ChatInput`: A struct for specifying the input parameters for a ChatGPT request.
+//! - `ClientBuilder`: A builder for configuring the base URL, headers and HTTP settings of a `Client`.
+//! - `ChatInput`: A struct for specifying the input parameters for a ChatGPT request, built with `ChatInput::builder()`.
+//! - `EmbeddingInput`: A struct for specifying the texts to embed with `Client::embeddings`.
 //! - `Message`, `Role`: Types related to messages and roles in conversations.
 //! - `Model`: Enum representing the available ChatGPT models.
 //! - `Response`: A struct representing the ChatGPT API response.
 //! - `TokenUsage`: A struct containing information about token usage in the response.
+//! - `json_stream`: Incremental parsing of streamed JSON output (when using the `stream` feature).
+//! - `segmenter`: Strategies for splitting streamed content with `Client::compress` (when using the `stream` feature).
+//! - `store`: Persistent storage of conversations in JSON files or SQLite (when using the `sqlite` feature).
+//! - `tokenizer`: A BPE tokenizer for counting tokens on the client side (when using the `tokenizer` feature).
+//! - `vector_index`: A local vector index retrieving passages for retrieval-augmented chat.
 //!
 //! # Example
 //!
-//! ```rust
-//! use chatgpt::Client;
-//! use chatgpt::types::{ChatInput, Message, Model, Role};
+//! ```rust,no_run
+//! use chatgpt_client::Client;
+//! use chatgpt_client::types::{ChatInput, Message, Model, Role};
 //!
 //! #[tokio::main]
 //! async fn main() {
 //!     let api_key = std::env::var("OPENAI_API_KEY").unwrap();
-//!     let client = Client::new(api_key);
+//!     let client = Client::new(api_key).unwrap();
 //!
-//!     let input = ChatInput {
-//!         model: Model::Gpt4,
-//!         messages: &[
-//!             Message::user("What is the capital of France?"),
-//!             Message::assistant("The capital of France is Paris."),
-//!             Message::user("Tell me more about Paris."),
-//!         ],
-//!         ..Default::default()
-//!     };
+//!     let input = ChatInput::builder()
+//!         .model(Model::Gpt4)
+//!         .message(Message::user("What is the capital of France?"))
+//!         .assistant("The capital of France is Paris.")
+//!         .user("Tell me more about Paris.")
+//!         .build();
 //!
 //!     let response = client.completion(&input).await.unwrap();
 //!     let assistant_message = &response.choices[0].message;
@@ -39,9 +43,23 @@
 //! ```
 
 pub mod err;
+#[cfg(feature = "stream")]
+pub mod json_stream;
+#[cfg(feature = "stream")]
+pub mod segmenter;
+pub mod store;
+#[cfg(feature = "tokenizer")]
+pub mod tokenizer;
 pub mod types;
+pub mod vector_index;
 
-pub use err::Error;
-pub use types::{ChatInput, Client, Message, Model, Response, Role, TokenUsage};
+#[cfg(feature = "stream")]
+pub use err::StreamTimeoutKind;
+pub use err::{ApiError, ApiErrorKind, Error};
+pub use types::{
+    ChatInput, Client, ClientBuilder, Conversation, EmbeddingInput, LogitBias, Message, Model,
+    Response, ResponseFormat, RetryPolicy, Role, TokenUsage, Tool, ToolCall, ToolChoice,
+    ToolRegistry,
+};
 
 pub type Result<T> = std::result::Result<T, Error>;
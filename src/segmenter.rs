@@ -0,0 +1,583 @@
+//! Strategies for splitting streamed content into segments with `Client::compress` (when using
+//! the `stream` feature).
+//!
+//! The segmenters defined in this module include:
+//! - `Delimiter`: Splits at a literal delimiter, such as `"\n\n"` for paragraphs.
+//! - `Lines`: Splits into lines, such as the records of JSON Lines output.
+//! - `Sentences`: Splits after sentence-ending punctuation.
+//! - `RegexSplit`: Splits at the matches of a regular expression.
+//! - `MaxChars`: Splits into segments of at most a given number of characters.
+//! - `IdleFlush`: Flushes the content buffered by another segmenter when the stream stalls.
+//!
+//! A string such as `"\n"` can be passed to `Client::compress` in place of a segmenter, to split
+//! at that delimiter.
+//!
+//! Content arrives as `String` fragments that may end anywhere, including in the middle of a
+//! delimiter. Segmenters buffer the fragments and only emit a segment once its end is certain,
+//! and always split at character boundaries.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use chatgpt_client::segmenter::{IdleFlush, Sentences};
+//! use chatgpt_client::types::StreamItem;
+//! use chatgpt_client::{ChatInput, Client};
+//! use futures::StreamExt;
+//! use std::time::Duration;
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let client = Client::new(std::env::var("OPENAI_API_KEY").unwrap()).unwrap();
+//!     let input = ChatInput::builder().user("Tell me a story.").stream(true).build();
+//!
+//!     let segmenter = IdleFlush::new(Sentences::new(), Duration::from_secs(2));
+//!     let mut stream = std::pin::pin!(client.compress(&input, segmenter).await.unwrap());
+//!     while let Some(item) = stream.next().await {
+//!         if let StreamItem::Content { content, .. } = item.unwrap() {
+//!             println!("{content}");
+//!         }
+//!     }
+//! }
+//! ```
+
+use std::time::Duration;
+
+/// A strategy for splitting the content of a streamed choice into segments.
+///
+/// `Client::compress` clones the segmenter for every choice of the response, feeds it the content
+/// fragments of that choice as they arrive, and flushes it when the choice finishes.
+pub trait Segmenter: Clone + Send {
+    /// Adds a content fragment and returns the segments it completes.
+    ///
+    /// # Arguments
+    ///
+    /// * `content` - The next content fragment.
+    ///
+    /// # Returns
+    ///
+    /// The completed segments, in order.
+    fn push(&mut self, content: &str) -> Vec<String>;
+
+    /// Returns the buffered content as a final segment, leaving the segmenter empty.
+    ///
+    /// # Returns
+    ///
+    /// An `Option` containing the buffered content, or `None` if there is nothing to emit.
+    fn flush(&mut self) -> Option<String>;
+
+    /// Returns how long the stream may stall before the buffered content is flushed.
+    ///
+    /// # Returns
+    ///
+    /// An `Option` containing the idle timeout, or `None` to never flush early. The default
+    /// implementation returns `None`.
+    fn idle_timeout(&self) -> Option<Duration> {
+        None
+    }
+}
+
+/// Conversion into a `Segmenter`, accepted by `Client::compress`.
+///
+/// Every `Segmenter` converts into itself, while string delimiters such as `"\n"` convert into
+/// a `Delimiter`.
+pub trait IntoSegmenter {
+    /// The segmenter produced by the conversion.
+    type Segmenter: Segmenter;
+
+    /// Converts the value into a `Segmenter`.
+    fn into_segmenter(self) -> Self::Segmenter;
+}
+
+impl<S: Segmenter> IntoSegmenter for S {
+    type Segmenter = S;
+
+    fn into_segmenter(self) -> S {
+        self
+    }
+}
+
+impl IntoSegmenter for &str {
+    type Segmenter = Delimiter;
+
+    fn into_segmenter(self) -> Delimiter {
+        Delimiter::new(self)
+    }
+}
+
+impl IntoSegmenter for String {
+    type Segmenter = Delimiter;
+
+    fn into_segmenter(self) -> Delimiter {
+        Delimiter::new(self)
+    }
+}
+
+/// Takes the buffered content, or `None` if it is empty.
+fn take_nonempty(buffer: &mut String) -> Option<String> {
+    Some(std::mem::take(buffer)).filter(|content| !content.is_empty())
+}
+
+/// Emits every content fragment as is, as `Client::stream` does.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct Passthrough;
+
+impl Segmenter for Passthrough {
+    fn push(&mut self, content: &str) -> Vec<String> {
+        vec![content.to_string()]
+    }
+
+    fn flush(&mut self) -> Option<String> {
+        None
+    }
+}
+
+/// Splits content at a literal delimiter, which is not included in the segments.
+#[derive(Debug, Clone)]
+pub struct Delimiter {
+    delimiter: String,
+    buffer: String,
+}
+
+impl Delimiter {
+    /// Creates a `Delimiter` segmenter.
+    ///
+    /// # Arguments
+    ///
+    /// * `delimiter` - The delimiter separating segments. An empty delimiter emits every
+    ///   fragment as a segment.
+    ///
+    /// # Returns
+    ///
+    /// A new `Delimiter` instance.
+    pub fn new(delimiter: impl Into<String>) -> Self {
+        Self {
+            delimiter: delimiter.into(),
+            buffer: String::new(),
+        }
+    }
+}
+
+impl From<&str> for Delimiter {
+    fn from(delimiter: &str) -> Self {
+        Self::new(delimiter)
+    }
+}
+
+impl Segmenter for Delimiter {
+    fn push(&mut self, content: &str) -> Vec<String> {
+        self.buffer.push_str(content);
+        if self.delimiter.is_empty() {
+            return take_nonempty(&mut self.buffer).into_iter().collect();
+        }
+
+        let mut segments = Vec::new();
+        while let Some(position) = self.buffer.find(&self.delimiter) {
+            segments.push(self.buffer[..position].to_string());
+            self.buffer.drain(..position + self.delimiter.len());
+        }
+        segments
+    }
+
+    fn flush(&mut self) -> Option<String> {
+        take_nonempty(&mut self.buffer)
+    }
+}
+
+/// Splits content into lines, without their `\n` or `\r\n` terminators. Blank lines are skipped,
+/// so each segment of JSON Lines output is one record.
+#[derive(Debug, Default, Clone)]
+pub struct Lines {
+    buffer: String,
+}
+
+impl Lines {
+    /// Creates a `Lines` segmenter.
+    ///
+    /// # Returns
+    ///
+    /// A new `Lines` instance.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Segmenter for Lines {
+    fn push(&mut self, content: &str) -> Vec<String> {
+        self.buffer.push_str(content);
+        let mut segments = Vec::new();
+        while let Some(position) = self.buffer.find('\n') {
+            let line = self.buffer[..position].trim_end_matches('\r');
+            if !line.trim().is_empty() {
+                segments.push(line.to_string());
+            }
+            self.buffer.drain(..=position);
+        }
+        segments
+    }
+
+    fn flush(&mut self) -> Option<String> {
+        let line = std::mem::take(&mut self.buffer);
+        Some(line.trim_end_matches('\r').to_string()).filter(|line| !line.trim().is_empty())
+    }
+}
+
+/// Splits content into sentences, for pipelines such as text-to-speech.
+///
+/// A sentence ends at a line break, or at a `.`, `!` or `?` followed by whitespace, allowing for
+/// closing quotes and brackets in between. The full-width `。`, `！` and `？` end a sentence
+/// without trailing whitespace. Whitespace around sentences is trimmed.
+#[derive(Debug, Default, Clone)]
+pub struct Sentences {
+    buffer: String,
+}
+
+impl Sentences {
+    /// Creates a `Sentences` segmenter.
+    ///
+    /// # Returns
+    ///
+    /// A new `Sentences` instance.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Finds the end of the first sentence of the buffer, if it is certain.
+    fn sentence_end(&self) -> Option<usize> {
+        let mut chars = self.buffer.char_indices().peekable();
+        while let Some((position, char)) = chars.next() {
+            let full_width = matches!(char, '。' | '！' | '？');
+            if char == '\n' {
+                return Some(position + 1);
+            }
+            if !full_width && !matches!(char, '.' | '!' | '?') {
+                continue;
+            }
+
+            let mut end = position + char.len_utf8();
+            while let Some(&(position, char)) = chars.peek() {
+                if !matches!(char, '"' | '\'' | ')' | ']' | '”' | '’' | '」' | '』') {
+                    break;
+                }
+                end = position + char.len_utf8();
+                chars.next();
+            }
+            match chars.peek() {
+                _ if full_width => return Some(end),
+                Some((_, char)) if char.is_whitespace() => return Some(end),
+                // The next fragment decides whether this is the end of a sentence.
+                None => return None,
+                Some(_) => {}
+            }
+        }
+        None
+    }
+}
+
+impl Segmenter for Sentences {
+    fn push(&mut self, content: &str) -> Vec<String> {
+        self.buffer.push_str(content);
+        let mut segments = Vec::new();
+        while let Some(end) = self.sentence_end() {
+            let sentence = self.buffer[..end].trim();
+            if !sentence.is_empty() {
+                segments.push(sentence.to_string());
+            }
+            self.buffer.drain(..end);
+        }
+        segments
+    }
+
+    fn flush(&mut self) -> Option<String> {
+        let sentence = std::mem::take(&mut self.buffer);
+        Some(sentence.trim().to_string()).filter(|sentence| !sentence.is_empty())
+    }
+}
+
+/// Splits content at the matches of a regular expression, which are not included in the
+/// segments.
+///
+/// A match touching the end of the buffered content is only used once more content arrives, as
+/// the next fragment could extend it.
+#[derive(Debug, Clone)]
+pub struct RegexSplit {
+    regex: regex::Regex,
+    buffer: String,
+}
+
+impl RegexSplit {
+    /// Creates a `RegexSplit` segmenter.
+    ///
+    /// # Arguments
+    ///
+    /// * `regex` - The regular expression matching the separators between segments.
+    ///
+    /// # Returns
+    ///
+    /// A new `RegexSplit` instance.
+    pub fn new(regex: regex::Regex) -> Self {
+        Self {
+            regex,
+            buffer: String::new(),
+        }
+    }
+}
+
+impl Segmenter for RegexSplit {
+    fn push(&mut self, content: &str) -> Vec<String> {
+        self.buffer.push_str(content);
+        let mut segments = Vec::new();
+        let mut start = 0;
+        // Empty matches, as of `\s*`, separate nothing.
+        for separator in self.regex.find_iter(&self.buffer) {
+            if separator.is_empty() {
+                continue;
+            }
+            if separator.end() == self.buffer.len() {
+                break;
+            }
+            segments.push(self.buffer[start..separator.start()].to_string());
+            start = separator.end();
+        }
+        self.buffer.drain(..start);
+        segments
+    }
+
+    fn flush(&mut self) -> Option<String> {
+        let rest = std::mem::take(&mut self.buffer);
+        // A separator at the very end was held back in case it continued.
+        let end = self
+            .regex
+            .find_iter(&rest)
+            .filter(|separator| !separator.is_empty() && separator.end() == rest.len())
+            .map(|separator| separator.start())
+            .next();
+        let rest = match end {
+            Some(end) => rest[..end].to_string(),
+            None => rest,
+        };
+        Some(rest).filter(|rest| !rest.is_empty())
+    }
+}
+
+/// Splits content into segments of at most a number of characters.
+///
+/// Segments break after the last whitespace within the limit, or at the limit within long
+/// words. No content is dropped, so the segments concatenate back to the content.
+#[derive(Debug, Clone)]
+pub struct MaxChars {
+    max_chars: usize,
+    buffer: String,
+}
+
+impl MaxChars {
+    /// Creates a `MaxChars` segmenter.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_chars` - The maximum number of characters of a segment, at least 1.
+    ///
+    /// # Returns
+    ///
+    /// A new `MaxChars` instance.
+    pub fn new(max_chars: usize) -> Self {
+        Self {
+            max_chars: max_chars.max(1),
+            buffer: String::new(),
+        }
+    }
+}
+
+impl Segmenter for MaxChars {
+    fn push(&mut self, content: &str) -> Vec<String> {
+        self.buffer.push_str(content);
+        let mut segments = Vec::new();
+        // The character after the limit decides whether the limit falls between words.
+        while let Some((limit, _)) = self.buffer.char_indices().nth(self.max_chars) {
+            let end = self.buffer[..limit]
+                .char_indices()
+                .rfind(|(_, char)| char.is_whitespace())
+                .map(|(position, char)| position + char.len_utf8())
+                .unwrap_or(limit);
+            segments.push(self.buffer.drain(..end).collect());
+        }
+        segments
+    }
+
+    fn flush(&mut self) -> Option<String> {
+        take_nonempty(&mut self.buffer)
+    }
+}
+
+/// Flushes the content buffered by another segmenter when no content arrives for a while, so a
+/// stalled stream does not hold back a partial segment.
+#[derive(Debug, Clone)]
+pub struct IdleFlush<S> {
+    inner: S,
+    timeout: Duration,
+}
+
+impl<S: Segmenter> IdleFlush<S> {
+    /// Creates an `IdleFlush` segmenter.
+    ///
+    /// # Arguments
+    ///
+    /// * `inner` - The segmenter splitting the content.
+    /// * `timeout` - How long the stream may stall before the buffered content is flushed.
+    ///
+    /// # Returns
+    ///
+    /// A new `IdleFlush` instance.
+    pub fn new(inner: S, timeout: Duration) -> Self {
+        Self { inner, timeout }
+    }
+}
+
+impl<S: Segmenter> Segmenter for IdleFlush<S> {
+    fn push(&mut self, content: &str) -> Vec<String> {
+        self.inner.push(content)
+    }
+
+    fn flush(&mut self) -> Option<String> {
+        self.inner.flush()
+    }
+
+    fn idle_timeout(&self) -> Option<Duration> {
+        Some(self.timeout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Feeds the fragments to the segmenter and flushes it.
+    fn segment(mut segmenter: impl Segmenter, fragments: &[&str]) -> Vec<String> {
+        let mut segments = Vec::new();
+        for fragment in fragments {
+            segments.extend(segmenter.push(fragment));
+        }
+        segments.extend(segmenter.flush());
+        segments
+    }
+
+    /// Segments the content whole, then one character at a time, and checks both agree.
+    fn segment_all_ways(segmenter: impl Segmenter, content: &str) -> Vec<String> {
+        let whole = segment(segmenter.clone(), &[content]);
+        let chars: Vec<String> = content.chars().map(String::from).collect();
+        let fragments: Vec<&str> = chars.iter().map(String::as_str).collect();
+        assert_eq!(segment(segmenter, &fragments), whole, "{content:?}");
+        whole
+    }
+
+    #[test]
+    fn delimiter_splits_across_fragments() {
+        assert_eq!(
+            segment(
+                Delimiter::new("\n\n"),
+                &["one\n", "\ntwo\n", "\n", "\nthree"]
+            ),
+            ["one", "two", "\nthree"]
+        );
+        assert_eq!(
+            segment_all_ways(Delimiter::from("||"), "a||b|c||"),
+            ["a", "b|c"]
+        );
+        assert_eq!(segment(Delimiter::new(""), &["a", "", "b"]), ["a", "b"]);
+        assert_eq!("\n".into_segmenter().push("a\nb"), ["a"]);
+        assert_eq!(String::from(",").into_segmenter().push("a,b"), ["a"]);
+    }
+
+    #[test]
+    fn lines_skip_blank_lines_and_carriage_returns() {
+        assert_eq!(
+            segment_all_ways(Lines::new(), "{\"a\":1}\r\n\n  \n{\"b\":2}\n{\"c\":3}\r"),
+            ["{\"a\":1}", "{\"b\":2}", "{\"c\":3}"]
+        );
+        assert_eq!(segment(Lines::new(), &["a\r", "\nb"]), ["a", "b"]);
+        assert!(segment(Lines::new(), &["\n", " \r\n"]).is_empty());
+    }
+
+    #[test]
+    fn sentences_end_at_punctuation_followed_by_whitespace() {
+        assert_eq!(
+            segment_all_ways(
+                Sentences::new(),
+                "Hello there. Version 1.5 is out! \"Really?\" she asked.\nYes"
+            ),
+            [
+                "Hello there.",
+                "Version 1.5 is out!",
+                "\"Really?\"",
+                "she asked.",
+                "Yes"
+            ]
+        );
+        assert_eq!(
+            segment_all_ways(Sentences::new(), "你好。再见！(Done.) Ok"),
+            ["你好。", "再见！", "(Done.)", "Ok"]
+        );
+        // The end of a sentence is only certain once the next character arrives.
+        let mut sentences = Sentences::new();
+        assert!(sentences.push("Wait.").is_empty());
+        assert_eq!(sentences.push(" Go"), ["Wait."]);
+        assert_eq!(sentences.flush().as_deref(), Some("Go"));
+    }
+
+    #[test]
+    fn regex_split_skips_empty_matches() {
+        let regex = |pattern| RegexSplit::new(regex::Regex::new(pattern).unwrap());
+        assert_eq!(
+            segment_all_ways(regex(r"\s*;\s*"), "a ; b;c ;"),
+            ["a", "b", "c"]
+        );
+        assert_eq!(
+            segment_all_ways(regex(r",?"), "a,b,,c"),
+            ["a", "b", "", "c"]
+        );
+        assert_eq!(
+            segment_all_ways(regex(r"\s*"), "one two  three"),
+            ["one", "two", "three"]
+        );
+        // A separator at the end of the buffer could still grow.
+        let mut split = regex(r"-+");
+        assert!(split.push("a--").is_empty());
+        assert_eq!(split.push("-b"), ["a"]);
+        assert_eq!(split.flush().as_deref(), Some("b"));
+    }
+
+    #[test]
+    fn max_chars_breaks_at_whitespace_without_losing_content() {
+        let content = "The quick brown fox jumps over the lazy dog";
+        let segments = segment_all_ways(MaxChars::new(10), content);
+        assert_eq!(
+            segments,
+            [
+                "The quick ",
+                "brown fox ",
+                "jumps ",
+                "over the ",
+                "lazy dog"
+            ]
+        );
+        assert_eq!(segments.concat(), content);
+        assert_eq!(
+            segment_all_ways(MaxChars::new(3), "ééééééé"),
+            ["ééé", "ééé", "é"]
+        );
+        assert_eq!(segment(MaxChars::new(0), &["ab"]), ["a", "b"]);
+    }
+
+    #[test]
+    fn idle_flush_delegates_to_its_segmenter() {
+        let mut segmenter = IdleFlush::new(Lines::new(), Duration::from_secs(2));
+        assert_eq!(segmenter.idle_timeout(), Some(Duration::from_secs(2)));
+        assert_eq!(segmenter.push("a\nb"), ["a"]);
+        assert_eq!(segmenter.flush().as_deref(), Some("b"));
+        assert_eq!(Lines::new().idle_timeout(), None);
+    }
+
+    #[test]
+    fn passthrough_emits_every_fragment() {
+        assert_eq!(segment(Passthrough, &["a", "b"]), ["a", "b"]);
+    }
+}
@@ -0,0 +1,78 @@
+//! This module defines the persistent storage of conversations.
+//!
+//! The storage backends defined in this module include:
+//! - `JsonFileStore`: Stores each conversation as a JSON file in a directory.
+//! - `SqliteStore`: Stores conversations in a SQLite database (when using the `sqlite` feature).
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use chatgpt_client::store::{ConversationStore, JsonFileStore};
+//! use chatgpt_client::types::Conversation;
+//! use chatgpt_client::ChatInput;
+//!
+//! let store = JsonFileStore::new("conversations").unwrap();
+//! let conversation = Conversation::new(ChatInput::builder().system("Hello!").build());
+//! store.save(&conversation.record("support-42")).unwrap();
+//!
+//! let record = store.load("support-42").unwrap().unwrap();
+//! let conversation = Conversation::from_record(record);
+//! ```
+
+pub mod json_file;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+
+pub use json_file::JsonFileStore;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteStore;
+
+use crate::types::{ConversationMetadata, ConversationRecord};
+use crate::Result;
+
+/// A storage backend that saves and loads conversations by identifier.
+///
+/// Backends are synchronous, as they only access local storage; wrap calls in
+/// `tokio::task::spawn_blocking` where blocking the executor is a concern.
+pub trait ConversationStore {
+    /// Saves a conversation, replacing any conversation stored under the same identifier.
+    ///
+    /// # Arguments
+    ///
+    /// * `record` - The state of the conversation, as returned by `Conversation::record`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` that is an `Error` if the conversation could not be saved.
+    fn save(&self, record: &ConversationRecord) -> Result<()>;
+
+    /// Loads a conversation.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The identifier of the conversation.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `ConversationRecord`, or `None` if no conversation is stored
+    /// under the identifier.
+    fn load(&self, id: &str) -> Result<Option<ConversationRecord>>;
+
+    /// Lists the metadata of all of the stored conversations, most recently updated first.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `ConversationMetadata` of each conversation or an `Error`.
+    fn list(&self) -> Result<Vec<ConversationMetadata>>;
+
+    /// Deletes a conversation.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The identifier of the conversation.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing `true` if a conversation was deleted.
+    fn delete(&self, id: &str) -> Result<bool>;
+}
@@ -0,0 +1,120 @@
+use crate::store::ConversationStore;
+use crate::types::{ConversationMetadata, ConversationRecord};
+use crate::{Error, Result};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+/// A `ConversationStore` that stores each conversation as a `<id>.json` file in a directory.
+///
+/// Files that cannot be read or parsed are left out of `list`, and fail in `load`.
+#[derive(Debug, Clone)]
+pub struct JsonFileStore {
+    dir: PathBuf,
+}
+
+impl JsonFileStore {
+    /// Creates a `JsonFileStore` in the given directory, creating the directory if needed.
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - The directory holding the conversation files.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a new `JsonFileStore` instance or an `Error`.
+    pub fn new(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    /// Returns the directory holding the conversation files.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Returns the path of the file of a conversation, rejecting identifiers that are not plain
+    /// file names.
+    fn path(&self, id: &str) -> Result<PathBuf> {
+        let valid = !id.is_empty() && id != "." && id != ".." && !id.contains(['/', '\\', '\0']);
+        if valid {
+            Ok(self.dir.join(format!("{id}.json")))
+        } else {
+            Err(Error::InvalidConversationId(id.to_string()))
+        }
+    }
+}
+
+impl ConversationStore for JsonFileStore {
+    fn save(&self, record: &ConversationRecord) -> Result<()> {
+        let path = self.path(&record.metadata.id)?;
+        let temporary = path.with_extension("json.tmp");
+        fs::write(&temporary, serde_json::to_vec_pretty(record)?)?;
+        fs::rename(&temporary, &path)?;
+        Ok(())
+    }
+
+    fn load(&self, id: &str) -> Result<Option<ConversationRecord>> {
+        match fs::read(self.path(id)?) {
+            Ok(content) => Ok(Some(serde_json::from_slice(&content)?)),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn list(&self) -> Result<Vec<ConversationMetadata>> {
+        let mut conversations = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path
+                .extension()
+                .is_some_and(|extension| extension == "json")
+            {
+                // A corrupt or unreadable file must not hide the other conversations.
+                let record = fs::read(&path).ok().and_then(|content| {
+                    serde_json::from_slice::<ConversationRecord>(&content).ok()
+                });
+                if let Some(record) = record {
+                    conversations.push(record.metadata);
+                }
+            }
+        }
+        conversations.sort_by_key(|metadata| std::cmp::Reverse(metadata.updated_at));
+        Ok(conversations)
+    }
+
+    fn delete(&self, id: &str) -> Result<bool> {
+        match fs::remove_file(self.path(id)?) {
+            Ok(()) => Ok(true),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(false),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Conversation;
+    use crate::ChatInput;
+
+    #[test]
+    fn list_skips_corrupt_files() {
+        let dir = std::env::temp_dir().join(format!("json-file-store-{}", fastrand::u64(..)));
+        let store = JsonFileStore::new(&dir).unwrap();
+        let conversation = Conversation::new(ChatInput::builder().user("Hello!").build());
+        store.save(&conversation.record("valid")).unwrap();
+        fs::write(dir.join("corrupt.json"), "{not json").unwrap();
+
+        let ids: Vec<String> = store
+            .list()
+            .unwrap()
+            .into_iter()
+            .map(|metadata| metadata.id)
+            .collect();
+        assert_eq!(ids, ["valid"]);
+        assert!(store.load("corrupt").is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,168 @@
+use crate::store::ConversationStore;
+use crate::types::{ConversationMetadata, ConversationRecord, TokenUsage};
+use crate::Result;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use std::path::Path;
+use std::sync::Mutex;
+
+/// A `ConversationStore` that stores conversations in a SQLite database (when using the `sqlite`
+/// feature).
+///
+/// Conversations are stored in a `conversations` table, which is created if needed. The
+/// metadata is kept in columns, so that listing does not load the messages.
+#[derive(Debug)]
+pub struct SqliteStore {
+    connection: Mutex<Connection>,
+}
+
+impl SqliteStore {
+    /// Opens or creates a `SqliteStore` in the database file at the given path.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the database file.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a new `SqliteStore` instance or an `Error`.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::from_connection(Connection::open(path)?)
+    }
+
+    /// Creates a `SqliteStore` in a new in-memory database.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a new `SqliteStore` instance or an `Error`.
+    pub fn open_in_memory() -> Result<Self> {
+        Self::from_connection(Connection::open_in_memory()?)
+    }
+
+    /// Creates a `SqliteStore` on an existing connection, creating its table if needed.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection` - The connection to the database.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a new `SqliteStore` instance or an `Error`.
+    pub fn from_connection(connection: Connection) -> Result<Self> {
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS conversations (
+                id TEXT PRIMARY KEY,
+                model TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL,
+                prompt_tokens INTEGER NOT NULL,
+                completion_tokens INTEGER NOT NULL,
+                total_tokens INTEGER NOT NULL,
+                summary TEXT,
+                summarized INTEGER NOT NULL,
+                messages TEXT NOT NULL
+            )",
+        )?;
+        Ok(Self {
+            connection: Mutex::new(connection),
+        })
+    }
+
+    fn connection(&self) -> std::sync::MutexGuard<'_, Connection> {
+        self.connection
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+/// Reads the metadata columns of a row.
+fn metadata(row: &Row) -> rusqlite::Result<ConversationMetadata> {
+    Ok(ConversationMetadata {
+        id: row.get("id")?,
+        model: row.get::<_, String>("model")?.into(),
+        created_at: row.get("created_at")?,
+        updated_at: row.get("updated_at")?,
+        usage: TokenUsage {
+            prompt_tokens: row.get("prompt_tokens")?,
+            completion_tokens: row.get("completion_tokens")?,
+            total_tokens: row.get("total_tokens")?,
+        },
+    })
+}
+
+impl ConversationStore for SqliteStore {
+    fn save(&self, record: &ConversationRecord) -> Result<()> {
+        let ConversationRecord {
+            metadata,
+            messages,
+            summary,
+            summarized,
+        } = record;
+        self.connection().execute(
+            "INSERT OR REPLACE INTO conversations (
+                id, model, created_at, updated_at, prompt_tokens, completion_tokens,
+                total_tokens, summary, summarized, messages
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                metadata.id,
+                metadata.model.as_str(),
+                metadata.created_at,
+                metadata.updated_at,
+                metadata.usage.prompt_tokens,
+                metadata.usage.completion_tokens,
+                metadata.usage.total_tokens,
+                summary,
+                *summarized as u64,
+                serde_json::to_string(messages)?,
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn load(&self, id: &str) -> Result<Option<ConversationRecord>> {
+        let row = self
+            .connection()
+            .query_row(
+                "SELECT * FROM conversations WHERE id = ?1",
+                params![id],
+                |row| {
+                    Ok((
+                        metadata(row)?,
+                        row.get::<_, String>("messages")?,
+                        row.get::<_, Option<String>>("summary")?,
+                        row.get::<_, u64>("summarized")?,
+                    ))
+                },
+            )
+            .optional()?;
+
+        row.map(|(metadata, messages, summary, summarized)| {
+            Ok(ConversationRecord {
+                metadata,
+                messages: serde_json::from_str(&messages)?,
+                summary,
+                summarized: summarized as usize,
+            })
+        })
+        .transpose()
+    }
+
+    fn list(&self) -> Result<Vec<ConversationMetadata>> {
+        let connection = self.connection();
+        let mut statement = connection.prepare(
+            "SELECT id, model, created_at, updated_at, prompt_tokens, completion_tokens,
+                total_tokens
+            FROM conversations ORDER BY updated_at DESC",
+        )?;
+        let conversations = statement
+            .query_map([], metadata)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(conversations)
+    }
+
+    fn delete(&self, id: &str) -> Result<bool> {
+        let deleted = self
+            .connection()
+            .execute("DELETE FROM conversations WHERE id = ?1", params![id])?;
+        Ok(deleted > 0)
+    }
+}
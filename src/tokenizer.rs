@@ -0,0 +1,346 @@
+//! A byte pair encoding (BPE) tokenizer for counting tokens on the client side (when using the
+//! `tokenizer` feature).
+//!
+//! The tokenizer implements the `cl100k_base` and `o200k_base` encodings used by the GPT-3.5,
+//! GPT-4 and GPT-4o models. Vocabularies are loaded from local files in the tiktoken format,
+//! where each line holds a base64-encoded token and its rank, such as the `cl100k_base.tiktoken`
+//! and `o200k_base.tiktoken` files published by OpenAI.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use chatgpt_client::tokenizer::{Encoding, Tokenizer};
+//! use chatgpt_client::{Message, Model};
+//!
+//! let tokenizer = Tokenizer::from_file(Encoding::Cl100kBase, "cl100k_base.tiktoken").unwrap();
+//! let tokens = tokenizer.encode("Hello, World!").unwrap();
+//! assert_eq!(tokenizer.decode(&tokens), "Hello, World!");
+//!
+//! let messages = [Message::user("What is the capital of France?")];
+//! let count = tokenizer.count_message_tokens(&messages, &Model::Gpt4).unwrap();
+//! println!("{count}");
+//! ```
+
+use crate::types::conversation::TOKENS_PER_REPLY;
+use crate::{Error, Message, Model, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use fancy_regex::Regex;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// The number of tokens added for every message by the chat format.
+const TOKENS_PER_MESSAGE: usize = 3;
+/// The number of tokens added when a message has a name.
+const TOKENS_PER_NAME: usize = 1;
+
+/// Represents the BPE encodings supported by the `Tokenizer`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    /// The encoding used by the GPT-3.5 and GPT-4 models.
+    Cl100kBase,
+    /// The encoding used by the GPT-4o models.
+    O200kBase,
+}
+
+impl Encoding {
+    /// Returns the encoding used by a model.
+    ///
+    /// # Arguments
+    ///
+    /// * `model` - The model to look up.
+    ///
+    /// # Returns
+    ///
+    /// `Encoding::O200kBase` for the GPT-4o family and `Encoding::Cl100kBase` otherwise.
+    pub fn for_model(model: &Model) -> Self {
+        if model.as_str().starts_with("gpt-4o") {
+            Encoding::O200kBase
+        } else {
+            Encoding::Cl100kBase
+        }
+    }
+
+    /// Returns the name of the encoding, such as "cl100k_base".
+    pub fn name(&self) -> &'static str {
+        match self {
+            Encoding::Cl100kBase => "cl100k_base",
+            Encoding::O200kBase => "o200k_base",
+        }
+    }
+
+    /// Returns the pattern splitting text into the pieces that are encoded separately.
+    fn pattern(&self) -> &'static str {
+        match self {
+            Encoding::Cl100kBase => concat!(
+                r"(?i:'s|'t|'re|'ve|'m|'ll|'d)|[^\r\n\p{L}\p{N}]?\p{L}+|\p{N}{1,3}",
+                r"| ?[^\s\p{L}\p{N}]+[\r\n]*|\s*[\r\n]+|\s+(?!\S)|\s+",
+            ),
+            Encoding::O200kBase => concat!(
+                r"[^\r\n\p{L}\p{N}]?[\p{Lu}\p{Lt}\p{Lm}\p{Lo}\p{M}]*[\p{Ll}\p{Lm}\p{Lo}\p{M}]+",
+                r"(?i:'s|'t|'re|'ve|'m|'ll|'d)?",
+                r"|[^\r\n\p{L}\p{N}]?[\p{Lu}\p{Lt}\p{Lm}\p{Lo}\p{M}]+[\p{Ll}\p{Lm}\p{Lo}\p{M}]*",
+                r"(?i:'s|'t|'re|'ve|'m|'ll|'d)?",
+                r"|\p{N}{1,3}| ?[^\s\p{L}\p{N}]+[\r\n/]*|\s*[\r\n]+|\s+(?!\S)|\s+",
+            ),
+        }
+    }
+}
+
+/// A BPE tokenizer for one of the supported `Encoding`s.
+#[derive(Debug)]
+pub struct Tokenizer {
+    encoding: Encoding,
+    pattern: Regex,
+    encoder: HashMap<Vec<u8>, u32>,
+    decoder: HashMap<u32, Vec<u8>>,
+}
+
+impl Tokenizer {
+    /// Creates a `Tokenizer` from the content of a vocabulary in the tiktoken format.
+    ///
+    /// # Arguments
+    ///
+    /// * `encoding` - The encoding the vocabulary belongs to.
+    /// * `vocabulary` - The lines of base64-encoded tokens and their ranks.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a new `Tokenizer` instance or an `Error` if the vocabulary is
+    /// malformed.
+    pub fn new(encoding: Encoding, vocabulary: &str) -> Result<Self> {
+        let mut encoder = HashMap::new();
+        for (number, line) in vocabulary.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let malformed =
+                || Error::TokenizerError(format!("Malformed vocabulary line {}", number + 1));
+            let (token, rank) = line.split_once(' ').ok_or_else(malformed)?;
+            let token = STANDARD.decode(token).map_err(|_| malformed())?;
+            let rank = rank.trim().parse::<u32>().map_err(|_| malformed())?;
+            encoder.insert(token, rank);
+        }
+        let decoder = encoder
+            .iter()
+            .map(|(token, &rank)| (rank, token.clone()))
+            .collect();
+        let pattern =
+            Regex::new(encoding.pattern()).map_err(|err| Error::TokenizerError(err.to_string()))?;
+
+        Ok(Self {
+            encoding,
+            pattern,
+            encoder,
+            decoder,
+        })
+    }
+
+    /// Creates a `Tokenizer` from a vocabulary file in the tiktoken format.
+    ///
+    /// # Arguments
+    ///
+    /// * `encoding` - The encoding the vocabulary belongs to.
+    /// * `path` - The path of the vocabulary file.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a new `Tokenizer` instance or an `Error`.
+    pub fn from_file(encoding: Encoding, path: impl AsRef<Path>) -> Result<Self> {
+        Self::new(encoding, &std::fs::read_to_string(path)?)
+    }
+
+    /// Returns the encoding of the tokenizer.
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// Encodes text into token ids. Special tokens are encoded as ordinary text.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to encode.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the token ids of the text, or an `Error::TokenizerError` if the text
+    /// could not be split into pieces, such as when the pattern exceeds its backtracking limit.
+    pub fn encode(&self, text: &str) -> Result<Vec<u32>> {
+        let mut tokens = Vec::new();
+        for piece in self.pattern.find_iter(text) {
+            let piece = piece.map_err(|err| Error::TokenizerError(err.to_string()))?;
+            let piece = piece.as_str().as_bytes();
+            match self.encoder.get(piece) {
+                Some(&token) => tokens.push(token),
+                None => tokens.extend(self.byte_pair_encode(piece)),
+            }
+        }
+        Ok(tokens)
+    }
+
+    /// Decodes token ids into text. Unknown ids are skipped and invalid UTF-8 is replaced.
+    ///
+    /// # Arguments
+    ///
+    /// * `tokens` - The token ids to decode.
+    ///
+    /// # Returns
+    ///
+    /// The decoded text.
+    pub fn decode(&self, tokens: &[u32]) -> String {
+        let bytes = tokens
+            .iter()
+            .filter_map(|token| self.decoder.get(token))
+            .flatten()
+            .copied()
+            .collect::<Vec<_>>();
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
+
+    /// Counts the tokens of a text, or returns an `Error::TokenizerError` if it cannot be encoded.
+    pub fn count_tokens(&self, text: &str) -> Result<usize> {
+        Ok(self.encode(text)?.len())
+    }
+
+    /// Counts the prompt tokens of a list of messages, including the overhead of the chat format.
+    ///
+    /// # Arguments
+    ///
+    /// * `messages` - The messages sent to the model.
+    /// * `model` - The model the messages are sent to.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the estimated number of prompt tokens, or an
+    /// `Error::TokenizerError` if a message cannot be encoded.
+    pub fn count_message_tokens<T: AsRef<str>>(
+        &self,
+        messages: &[Message<T>],
+        model: &Model,
+    ) -> Result<usize> {
+        let (tokens_per_message, tokens_per_name) = match model.as_str() {
+            "gpt-3.5-turbo-0301" => (4, 0),
+            _ => (TOKENS_PER_MESSAGE, TOKENS_PER_NAME),
+        };
+        let mut tokens = TOKENS_PER_REPLY;
+        for message in messages {
+            tokens += tokens_per_message + self.count_tokens(message.role.as_str())?;
+            tokens += self.count_tokens(message.content.as_ref())?;
+            if let Some(name) = &message.name {
+                tokens += self.count_tokens(name.as_ref())? + tokens_per_name;
+            }
+            if let Some(tool_call_id) = &message.tool_call_id {
+                tokens += self.count_tokens(tool_call_id.as_ref())?;
+            }
+            for tool_call in message.tool_calls.iter().flatten() {
+                tokens += self.count_tokens(&tool_call.function.name)?;
+                tokens += self.count_tokens(&tool_call.function.arguments)?;
+            }
+        }
+        Ok(tokens)
+    }
+
+    /// Merges the bytes of a piece into tokens, lowest-ranked pairs first.
+    fn byte_pair_encode(&self, piece: &[u8]) -> Vec<u32> {
+        let mut parts = piece.iter().map(|byte| vec![*byte]).collect::<Vec<_>>();
+        loop {
+            let best = parts
+                .windows(2)
+                .enumerate()
+                .filter_map(|(index, pair)| {
+                    self.encoder
+                        .get(&[pair[0].as_slice(), pair[1].as_slice()].concat())
+                        .map(|&rank| (rank, index))
+                })
+                .min();
+            match best {
+                Some((_, index)) => {
+                    let next = parts.remove(index + 1);
+                    parts[index].extend(next);
+                }
+                None => break,
+            }
+        }
+        parts
+            .iter()
+            .filter_map(|part| self.encoder.get(part).copied())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A vocabulary holding every byte, ranked by value, followed by a few merges.
+    fn tokenizer() -> Tokenizer {
+        let mut tokens: Vec<Vec<u8>> = (0..=255).map(|byte| vec![byte]).collect();
+        tokens.extend(["ll", "he", "hell", " w", " wo"].map(|token| token.as_bytes().to_vec()));
+        let vocabulary = tokens
+            .iter()
+            .enumerate()
+            .map(|(rank, token)| format!("{} {rank}", STANDARD.encode(token)))
+            .collect::<Vec<_>>()
+            .join("\n");
+        Tokenizer::new(Encoding::Cl100kBase, &vocabulary).unwrap()
+    }
+
+    #[test]
+    fn lowest_ranked_pairs_are_merged_first() {
+        let tokenizer = tokenizer();
+        // "ll" merges before "he", then both merge into "hell".
+        assert_eq!(tokenizer.encode("hello").unwrap(), vec![258, b'o' as u32]);
+        assert_eq!(
+            tokenizer.encode(" world").unwrap(),
+            vec![260, b'r' as u32, b'l' as u32, b'd' as u32]
+        );
+        assert_eq!(
+            tokenizer.encode("help").unwrap(),
+            vec![257, b'l' as u32, b'p' as u32]
+        );
+    }
+
+    #[test]
+    fn decode_reverses_encode() {
+        let tokenizer = tokenizer();
+        for text in [
+            "Hello, World!",
+            "hello world\n\n  it's 2024",
+            "héllo wörld 👋",
+            "",
+        ] {
+            let tokens = tokenizer.encode(text).unwrap();
+            assert_eq!(tokenizer.decode(&tokens), text);
+        }
+    }
+
+    #[test]
+    fn message_tokens_include_the_chat_format() {
+        let tokenizer = tokenizer();
+        let messages = [Message::user("hello")];
+        // 3 per message, 4 for "user", 2 for "hello" and 3 priming the reply.
+        assert_eq!(
+            tokenizer
+                .count_message_tokens(&messages, &Model::Gpt4)
+                .unwrap(),
+            12
+        );
+
+        let named = [Message {
+            name: Some("bob"),
+            ..Message::user("hello")
+        }];
+        assert_eq!(
+            tokenizer
+                .count_message_tokens(&named, &Model::Gpt4)
+                .unwrap(),
+            12 + 3 + TOKENS_PER_NAME
+        );
+    }
+
+    #[test]
+    fn malformed_vocabularies_are_rejected() {
+        assert!(Tokenizer::new(Encoding::Cl100kBase, "aGVsbG8=").is_err());
+        assert!(Tokenizer::new(Encoding::Cl100kBase, "aGVsbG8= one").is_err());
+        assert!(Tokenizer::new(Encoding::Cl100kBase, "!!! 1").is_err());
+    }
+}
@@ -1,30 +1,89 @@
 //! This module defines various types used throughout the ChatGPT API library.
 //!
 //! The types defined in this module include:
-//! - `ChatInput`: The input parameters for generating responses from the ChatGPT model.
-//! - `Chunk`, `ChunkChoice`, `Delta`: Types related to response streaming (when using the `stream` feature).
-//! - `Client`: The main ChatGPT API client.
+//! - `CancellableStream`, `CancelHandle`, `StreamTimeouts`: Cancellation and timeouts of streams (when using the `stream` feature).
+//! - `ChatInput`, `ChatInputBuilder`: The input parameters for generating responses from the ChatGPT model.
+//! - `StreamOptions`: The options of streamed responses, such as usage reporting (when using the `stream` feature).
+//! - `ChoiceStream`, `demultiplex`: Splitting a streamed response into one stream per choice (when using the `stream` feature).
+//! - `Chunk`, `ChunkChoice`, `Delta`, `ToolCallDelta`: Types related to response streaming (when using the `stream` feature).
+//! - `Client`, `ClientBuilder`: The main ChatGPT API client and its builder.
+//! - `Conversation`, `TrimStrategy`: A conversation that trims its history to fit the context window.
+//! - `EmbeddingInput`, `EmbeddingResponse`, `Embedding`: Types related to embeddings, with vector helpers.
+//! - `LogitBias`: A map from token ids to the bias added to their logits.
 //! - `Message`, `Role`: Types related to messages and roles in conversations.
 //! - `Model`: The available ChatGPT models.
+//! - `ModelInfo`, `ModelRegistry`: The context window, output limit and pricing of models.
 //! - `Response`, `Choice`, `TokenUsage`: Types related to ChatGPT responses.
+//! - `ResponseFormat`, `JsonSchema`: Types for requesting structured output.
+//! - `ResumePolicy`: Configuration for resuming interrupted streams (when using the `stream` feature).
+//! - `RetryPolicy`: Configuration for retrying transient request failures.
+//! - `Tool`, `ToolChoice`, `ToolCall`: Types related to tool (function) calling.
+//! - `ToolRegistry`, `ToolRun`: Types for executing tool calls with Rust functions.
+//! - `Summarizer`: Rolling summarization of the oldest turns of a `Conversation`.
 //! - `StreamItem`: Type for processing streamed responses (when using the `stream` feature).
+//! - `StreamAccumulator`: Reassembles a `Response` from streamed chunks (when using the `stream` feature).
 
+#[cfg(feature = "stream")]
+pub mod cancellable_stream;
 pub mod chat_input;
+pub mod chat_input_builder;
+#[cfg(feature = "stream")]
+pub mod choice_stream;
 #[cfg(feature = "stream")]
 pub mod chunk;
 pub mod client;
+pub mod client_builder;
+pub mod conversation;
+pub mod embedding;
+pub mod logit_bias;
 pub mod message;
 pub mod model;
+pub mod model_info;
 pub mod response;
+pub mod response_format;
+#[cfg(feature = "stream")]
+pub mod resume_policy;
+pub mod retry_policy;
+#[cfg(feature = "stream")]
+pub mod stream_accumulator;
 #[cfg(feature = "stream")]
 pub mod stream_item;
+pub mod summarizer;
+pub mod tool;
+pub mod tool_registry;
+#[cfg(feature = "stream")]
+pub use cancellable_stream::{CancelHandle, CancellableStream, StreamTimeouts};
 pub use chat_input::ChatInput;
+#[cfg(feature = "stream")]
+pub use chat_input::StreamOptions;
+pub use chat_input_builder::ChatInputBuilder;
+#[cfg(feature = "stream")]
+pub use choice_stream::{demultiplex, ChoiceStream};
 
 #[cfg(feature = "stream")]
-pub use chunk::{Chunk, ChunkChoice, Delta};
+pub use chunk::{Chunk, ChunkChoice, Delta, FunctionCallDelta, ToolCallDelta};
 pub use client::Client;
+pub use client_builder::ClientBuilder;
+pub use conversation::{
+    Conversation, ConversationMetadata, ConversationRecord, KeepAll, SlidingWindow, TokenBudget,
+    TokenCounter, TrimStrategy,
+};
+pub use embedding::{
+    cosine_similarity, dot, normalize, Embedding, EmbeddingInput, EmbeddingResponse, EncodingFormat,
+};
+pub use logit_bias::LogitBias;
 pub use message::{Message, Role};
 pub use model::Model;
+pub use model_info::{ModelInfo, ModelRegistry};
 pub use response::{Choice, Response, TokenUsage};
+pub use response_format::{JsonSchema, ResponseFormat};
+#[cfg(feature = "stream")]
+pub use resume_policy::ResumePolicy;
+pub use retry_policy::RetryPolicy;
+#[cfg(feature = "stream")]
+pub use stream_accumulator::StreamAccumulator;
 #[cfg(feature = "stream")]
 pub use stream_item::StreamItem;
+pub use summarizer::Summarizer;
+pub use tool::{FunctionCall, FunctionDefinition, Tool, ToolCall, ToolChoice};
+pub use tool_registry::{ToolRegistry, ToolRun};
@@ -0,0 +1,261 @@
+use crate::err::StreamTimeoutKind;
+use crate::types::StreamItem;
+use crate::{Error, Result};
+use futures::stream::{unfold, BoxStream, Stream, StreamExt};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::time::{sleep_until, Instant};
+
+/// The timeouts enforced on a `CancellableStream` (when using the `stream` feature).
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct StreamTimeouts {
+    /// The maximum duration of the whole stream.
+    pub total: Option<Duration>,
+    /// The maximum duration without receiving a token. Items carrying no content or tool
+    /// calls, such as keep-alive chunks, do not reset it.
+    pub idle: Option<Duration>,
+}
+
+impl StreamTimeouts {
+    /// Sets the maximum duration of the whole stream.
+    pub fn total(mut self, total: Duration) -> Self {
+        self.total = Some(total);
+        self
+    }
+
+    /// Sets the maximum duration without receiving a token.
+    pub fn idle(mut self, idle: Duration) -> Self {
+        self.idle = Some(idle);
+        self
+    }
+}
+
+/// A handle cancelling a `CancellableStream` from anywhere, such as the task watching for the
+/// disconnection of a user.
+#[derive(Debug, Clone)]
+pub struct CancelHandle {
+    sender: std::sync::Arc<watch::Sender<bool>>,
+}
+
+impl CancelHandle {
+    /// Cancels the stream, which ends without further items. The connection is closed, which
+    /// aborts the generation.
+    pub fn cancel(&self) {
+        self.sender.send_replace(true);
+    }
+
+    /// Returns `true` if the stream was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        *self.sender.borrow()
+    }
+}
+
+/// A stream of `StreamItem`s that can be cancelled and is subject to `StreamTimeouts` (when using
+/// the `stream` feature).
+///
+/// When a timeout elapses, the stream yields `Error::StreamTimeout` carrying the content received
+/// so far, then ends.
+pub struct CancellableStream {
+    inner: BoxStream<'static, Result<StreamItem>>,
+    handle: CancelHandle,
+}
+
+impl std::fmt::Debug for CancellableStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CancellableStream")
+            .field("handle", &self.handle)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The state of a `CancellableStream` between items.
+struct State {
+    stream: BoxStream<'static, Result<StreamItem>>,
+    receiver: watch::Receiver<bool>,
+    timeouts: StreamTimeouts,
+    deadline: Option<Instant>,
+    idle_deadline: Option<Instant>,
+    partial: Vec<String>,
+}
+
+impl State {
+    /// Restarts the idle timeout after a token was received.
+    fn reset_idle(&mut self) {
+        self.idle_deadline = self.timeouts.idle.map(|idle| Instant::now() + idle);
+    }
+}
+
+impl CancellableStream {
+    /// Wraps a stream of `StreamItem`s, starting the total and idle timeouts.
+    ///
+    /// # Arguments
+    ///
+    /// * `stream` - A stream of `StreamItem`s, such as the one returned by `Client::stream` or
+    ///   `Client::compress`.
+    /// * `timeouts` - The timeouts enforced on the stream.
+    ///
+    /// # Returns
+    ///
+    /// A new `CancellableStream` instance.
+    pub fn new<S>(stream: S, timeouts: StreamTimeouts) -> Self
+    where
+        S: Stream<Item = Result<StreamItem>> + Send + 'static,
+    {
+        let (sender, receiver) = watch::channel(false);
+        let state = State {
+            stream: stream.boxed(),
+            receiver,
+            timeouts,
+            deadline: timeouts.total.map(|total| Instant::now() + total),
+            idle_deadline: timeouts.idle.map(|idle| Instant::now() + idle),
+            partial: Vec::new(),
+        };
+
+        let inner = unfold(Some(state), |state| async move {
+            let mut state = state?;
+            let item = tokio::select! {
+                biased;
+                _ = cancelled(&mut state.receiver) => return None,
+                _ = wait(state.deadline.map(sleep_until)) => {
+                    timeout(StreamTimeoutKind::Total, state.timeouts.total, state.partial)
+                }
+                _ = wait(state.idle_deadline.map(sleep_until)) => {
+                    timeout(StreamTimeoutKind::Idle, state.timeouts.idle, state.partial)
+                }
+                item = state.stream.next() => {
+                    match &item {
+                        Some(Ok(StreamItem::Content { index, content })) => {
+                            if state.partial.len() <= *index {
+                                state.partial.resize(*index + 1, String::new());
+                            }
+                            state.partial[*index].push_str(content);
+                            state.reset_idle();
+                        }
+                        Some(Ok(StreamItem::ToolCalls { .. })) => state.reset_idle(),
+                        _ => {}
+                    }
+                    return item.map(|item| (item, Some(state)));
+                }
+            };
+            Some((item, None))
+        })
+        .boxed();
+
+        Self {
+            inner,
+            handle: CancelHandle {
+                sender: std::sync::Arc::new(sender),
+            },
+        }
+    }
+
+    /// Cancels the stream, which ends without further items.
+    pub fn cancel(&self) {
+        self.handle.cancel();
+    }
+
+    /// Returns a handle that cancels the stream from another task.
+    pub fn cancel_handle(&self) -> CancelHandle {
+        self.handle.clone()
+    }
+}
+
+impl Stream for CancellableStream {
+    type Item = Result<StreamItem>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.poll_next_unpin(cx)
+    }
+}
+
+/// Waits until the stream is cancelled.
+async fn cancelled(receiver: &mut watch::Receiver<bool>) {
+    while !*receiver.borrow_and_update() {
+        if receiver.changed().await.is_err() {
+            // Every handle is gone, so the stream can no longer be cancelled.
+            std::future::pending::<()>().await;
+        }
+    }
+}
+
+/// Waits for an optional timer, forever if there is none.
+async fn wait(timer: Option<impl std::future::Future<Output = ()>>) {
+    match timer {
+        Some(timer) => timer.await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Builds the error reporting an elapsed timeout with the content received so far.
+fn timeout(
+    kind: StreamTimeoutKind,
+    duration: Option<Duration>,
+    partial: Vec<String>,
+) -> Result<StreamItem> {
+    Err(Error::StreamTimeout {
+        kind,
+        duration: duration.unwrap_or_default(),
+        partial,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::stream;
+
+    /// A stream yielding one keep-alive item every 10ms.
+    fn keep_alives() -> impl Stream<Item = Result<StreamItem>> + Send + 'static {
+        stream::unfold((), |()| async {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            Some((Ok(StreamItem::Empty { index: Some(0) }), ()))
+        })
+    }
+
+    #[tokio::test]
+    async fn keep_alives_do_not_reset_the_idle_timeout() {
+        let timeouts = StreamTimeouts::default().idle(Duration::from_millis(50));
+        let items: Vec<_> = CancellableStream::new(keep_alives(), timeouts)
+            .collect()
+            .await;
+        assert!(matches!(
+            items.last(),
+            Some(Err(Error::StreamTimeout {
+                kind: StreamTimeoutKind::Idle,
+                ..
+            }))
+        ));
+    }
+
+    #[tokio::test]
+    async fn tokens_reset_the_idle_timeout() {
+        let tokens = stream::iter(0..10).then(|_| async {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            Ok(StreamItem::Content {
+                index: 0,
+                content: "token".to_string(),
+            })
+        });
+        let timeouts = StreamTimeouts::default().idle(Duration::from_millis(100));
+        let items: Vec<_> = CancellableStream::new(tokens, timeouts).collect().await;
+        assert_eq!(items.len(), 10);
+        assert!(items.iter().all(Result::is_ok));
+    }
+
+    #[tokio::test]
+    async fn idle_timeout_reports_partial_content() {
+        let tokens = stream::iter([Ok(StreamItem::Content {
+            index: 0,
+            content: "Hello".to_string(),
+        })])
+        .chain(keep_alives());
+        let timeouts = StreamTimeouts::default().idle(Duration::from_millis(50));
+        let items: Vec<_> = CancellableStream::new(tokens, timeouts).collect().await;
+        match items.last() {
+            Some(Err(Error::StreamTimeout { partial, .. })) => assert_eq!(partial, &["Hello"]),
+            item => panic!("unexpected {item:?}"),
+        }
+    }
+}
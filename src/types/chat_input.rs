@@ -1,14 +1,18 @@
-use crate::{Message, Model};
+use crate::types::{ChatInputBuilder, LogitBias, ResponseFormat, Tool, ToolChoice};
+use crate::{Message, Model, Result};
 use reqwest::Body;
 use serde::Serialize;
 
 /// Represents the input for a chat API call.
-#[derive(Debug, Serialize)]
-pub struct ChatInput<'a> {
+///
+/// `ChatInput` owns its messages, so it can be built in one task and sent from another. Use
+/// `ChatInput::builder()` to assemble one fluently.
+#[derive(Debug, Clone, Serialize)]
+pub struct ChatInput {
     /// The model to use for generating responses.
     pub model: Model,
     /// The array of messages to send as input.
-    pub messages: &'a [Message<&'a str>],
+    pub messages: Vec<Message<String>>,
     /// The optional temperature to use for controlling randomness.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub temperature: Option<f64>,
@@ -22,6 +26,10 @@ pub struct ChatInput<'a> {
     #[cfg(feature = "stream")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub stream: Option<bool>,
+    /// The optional options of the streamed response.
+    #[cfg(feature = "stream")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream_options: Option<StreamOptions>,
     /// The optional array of stop phrases.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub stop: Option<Vec<String>>,
@@ -34,15 +42,35 @@ pub struct ChatInput<'a> {
     /// The optional frequency penalty.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub frequency_penalty: Option<f64>,
-    /// The optional logit bias.
+    /// The optional map from token ids to the bias added to their logits.
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub logit_bias: Option<i16>,
+    pub logit_bias: Option<LogitBias>,
     /// The optional user identifier.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub user: Option<String>,
+    /// The optional list of tools the model may call.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<Tool>>,
+    /// The optional choice of which tool, if any, the model calls.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_choice: Option<ToolChoice>,
+    /// The optional flag allowing the model to make several tool calls at once.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parallel_tool_calls: Option<bool>,
+    /// The optional format the model must produce its output in.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<ResponseFormat>,
 }
 
-impl Default for ChatInput<'_> {
+/// Represents the options of a streamed response (when using the `stream` feature).
+#[cfg(feature = "stream")]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize)]
+pub struct StreamOptions {
+    /// Whether to stream a final chunk reporting the token usage of the whole request.
+    pub include_usage: bool,
+}
+
+impl Default for ChatInput {
     /// Provides default values for `ChatInput`.
     ///
     /// # Returns
@@ -51,23 +79,52 @@ impl Default for ChatInput<'_> {
     fn default() -> Self {
         Self {
             model: Model::default(),
-            messages: &[],
+            messages: Vec::new(),
             temperature: None,
             top_p: None,
             n: None,
             #[cfg(feature = "stream")]
             stream: None,
+            #[cfg(feature = "stream")]
+            stream_options: None,
             stop: None,
             max_tokens: None,
             presence_penalty: None,
             frequency_penalty: None,
             logit_bias: None,
             user: None,
+            tools: None,
+            tool_choice: None,
+            parallel_tool_calls: None,
+            response_format: None,
+        }
+    }
+}
+
+impl ChatInput {
+    /// Creates a `ChatInputBuilder` for assembling a `ChatInput` fluently.
+    ///
+    /// # Returns
+    ///
+    /// A new `ChatInputBuilder` instance.
+    pub fn builder() -> ChatInputBuilder {
+        ChatInputBuilder::default()
+    }
+
+    /// Checks the parameters that the API would reject, before the request is sent.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` that is an `Error` describing the first invalid parameter.
+    pub fn validate(&self) -> Result<()> {
+        if let Some(logit_bias) = &self.logit_bias {
+            logit_bias.validate()?;
         }
+        Ok(())
     }
 }
 
-impl From<&ChatInput<'_>> for Body {
+impl From<&ChatInput> for Body {
     /// Converts a `ChatInput` into a `Body` for use in an API request.
     ///
     /// # Arguments
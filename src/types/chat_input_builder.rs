@@ -0,0 +1,210 @@
+use crate::types::{LogitBias, ResponseFormat, Tool, ToolChoice};
+use crate::{ChatInput, Message, Model};
+
+#[cfg(feature = "stream")]
+use crate::types::StreamOptions;
+
+/// A builder for assembling a `ChatInput` fluently.
+///
+/// # Example
+///
+/// ```rust
+/// use chatgpt_client::{ChatInput, Model};
+///
+/// let input = ChatInput::builder()
+///     .model(Model::Gpt4)
+///     .system("You are a helpful assistant.")
+///     .user("What is the capital of France?")
+///     .temperature(0.2)
+///     .build();
+///
+/// assert_eq!(input.messages.len(), 2);
+/// ```
+#[derive(Debug, Default)]
+pub struct ChatInputBuilder {
+    input: ChatInput,
+}
+
+impl ChatInputBuilder {
+    /// Sets the model to use for generating responses, given as a `Model` or its identifier.
+    pub fn model(mut self, model: impl Into<Model>) -> Self {
+        self.input.model = model.into();
+        self
+    }
+
+    /// Appends a message.
+    pub fn message(mut self, message: impl Into<Message<String>>) -> Self {
+        self.input.messages.push(message.into());
+        self
+    }
+
+    /// Appends all of the given messages.
+    pub fn messages<I>(mut self, messages: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<Message<String>>,
+    {
+        self.input
+            .messages
+            .extend(messages.into_iter().map(Into::into));
+        self
+    }
+
+    /// Appends a message with the system role.
+    pub fn system(self, content: impl Into<String>) -> Self {
+        self.message(Message::system(content.into()))
+    }
+
+    /// Appends a message with the user role.
+    pub fn user(self, content: impl Into<String>) -> Self {
+        self.message(Message::user(content.into()))
+    }
+
+    /// Appends a message with the assistant role.
+    pub fn assistant(self, content: impl Into<String>) -> Self {
+        self.message(Message::assistant(content.into()))
+    }
+
+    /// Sets the temperature to use for controlling randomness.
+    pub fn temperature(mut self, temperature: f64) -> Self {
+        self.input.temperature = Some(temperature);
+        self
+    }
+
+    /// Sets the top_p value for controlling sampling.
+    pub fn top_p(mut self, top_p: f64) -> Self {
+        self.input.top_p = Some(top_p);
+        self
+    }
+
+    /// Sets the number of generated choices.
+    pub fn n(mut self, n: u8) -> Self {
+        self.input.n = Some(n);
+        self
+    }
+
+    /// Sets the streaming flag. Turning streaming off also clears the stream options, which the
+    /// API rejects without streaming.
+    #[cfg(feature = "stream")]
+    pub fn stream(mut self, stream: bool) -> Self {
+        self.input.stream = Some(stream);
+        if !stream {
+            self.input.stream_options = None;
+        }
+        self
+    }
+
+    /// Sets the options of the streamed response, which also enables streaming since the API
+    /// rejects stream options otherwise.
+    #[cfg(feature = "stream")]
+    pub fn stream_options(mut self, stream_options: StreamOptions) -> Self {
+        self.input.stream = Some(true);
+        self.input.stream_options = Some(stream_options);
+        self
+    }
+
+    /// Requests a final chunk reporting the token usage of the streamed response, which also
+    /// enables streaming.
+    #[cfg(feature = "stream")]
+    pub fn include_usage(self, include_usage: bool) -> Self {
+        self.stream_options(StreamOptions { include_usage })
+    }
+
+    /// Sets the stop phrases.
+    pub fn stop<I>(mut self, stop: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        self.input.stop = Some(stop.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Sets the maximum number of tokens in the response.
+    pub fn max_tokens(mut self, max_tokens: usize) -> Self {
+        self.input.max_tokens = Some(max_tokens);
+        self
+    }
+
+    /// Sets the presence penalty.
+    pub fn presence_penalty(mut self, presence_penalty: f64) -> Self {
+        self.input.presence_penalty = Some(presence_penalty);
+        self
+    }
+
+    /// Sets the frequency penalty.
+    pub fn frequency_penalty(mut self, frequency_penalty: f64) -> Self {
+        self.input.frequency_penalty = Some(frequency_penalty);
+        self
+    }
+
+    /// Sets the map from token ids to the bias added to their logits.
+    pub fn logit_bias(mut self, logit_bias: impl Into<LogitBias>) -> Self {
+        self.input.logit_bias = Some(logit_bias.into());
+        self
+    }
+
+    /// Sets the identifier of the end-user, which helps the API to monitor and detect abuse.
+    pub fn user_id(mut self, user: impl Into<String>) -> Self {
+        self.input.user = Some(user.into());
+        self
+    }
+
+    /// Appends a tool the model may call.
+    pub fn tool(mut self, tool: Tool) -> Self {
+        self.input.tools.get_or_insert_with(Vec::new).push(tool);
+        self
+    }
+
+    /// Appends all of the given tools.
+    pub fn tools(mut self, tools: impl IntoIterator<Item = Tool>) -> Self {
+        self.input.tools.get_or_insert_with(Vec::new).extend(tools);
+        self
+    }
+
+    /// Sets which tool, if any, the model calls.
+    pub fn tool_choice(mut self, tool_choice: ToolChoice) -> Self {
+        self.input.tool_choice = Some(tool_choice);
+        self
+    }
+
+    /// Sets whether the model may make several tool calls at once.
+    pub fn parallel_tool_calls(mut self, parallel_tool_calls: bool) -> Self {
+        self.input.parallel_tool_calls = Some(parallel_tool_calls);
+        self
+    }
+
+    /// Sets the format the model must produce its output in.
+    pub fn response_format(mut self, response_format: ResponseFormat) -> Self {
+        self.input.response_format = Some(response_format);
+        self
+    }
+
+    /// Builds the `ChatInput`.
+    ///
+    /// # Returns
+    ///
+    /// The assembled `ChatInput` instance.
+    pub fn build(self) -> ChatInput {
+        self.input
+    }
+}
+
+#[cfg(all(test, feature = "stream"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stream_options_follow_the_streaming_flag() {
+        let input = ChatInput::builder().include_usage(true).build();
+        assert_eq!(input.stream, Some(true));
+        assert!(input.stream_options.is_some());
+
+        let input = ChatInput::builder()
+            .include_usage(true)
+            .stream(false)
+            .build();
+        assert_eq!(input.stream, Some(false));
+        assert_eq!(input.stream_options, None);
+    }
+}
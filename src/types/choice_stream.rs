@@ -0,0 +1,157 @@
+use crate::types::StreamItem;
+use crate::{Error, Result};
+use futures::stream::{Stream, StreamExt};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+/// The stream of the `StreamItem`s of a single choice, split from a response with several
+/// choices by `demultiplex` (when using the `stream` feature).
+#[derive(Debug)]
+pub struct ChoiceStream {
+    index: usize,
+    receiver: UnboundedReceiver<Result<StreamItem>>,
+}
+
+impl ChoiceStream {
+    /// Returns the index of the choice carried by the stream.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+impl Stream for ChoiceStream {
+    type Item = Result<StreamItem>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_recv(cx)
+    }
+}
+
+/// Splits a stream of `StreamItem`s into one `ChoiceStream` per choice.
+///
+/// The stream is driven by a background task, so the choice streams can be consumed independently
+/// and at their own pace. The task stops reading the stream once every choice stream is dropped.
+/// Items of choices beyond `choices` are dropped, and items belonging to no choice are delivered to
+/// every choice stream. An error is delivered to every choice stream: the first one receives the
+/// original `Error` and the others an `Error::ResponseError` with the same message.
+///
+/// # Arguments
+///
+/// * `stream` - A stream of `StreamItem`s, such as the one returned by `Client::stream` or
+///   `Client::compress`.
+/// * `choices` - The number of choices of the response, usually `ChatInput::n`.
+///
+/// # Returns
+///
+/// The `ChoiceStream`s, ordered by choice index.
+pub fn demultiplex<S>(stream: S, choices: usize) -> Vec<ChoiceStream>
+where
+    S: Stream<Item = Result<StreamItem>> + Send + 'static,
+{
+    let (senders, streams): (Vec<UnboundedSender<_>>, Vec<_>) = (0..choices)
+        .map(|index| {
+            let (sender, receiver) = unbounded_channel();
+            (sender, ChoiceStream { index, receiver })
+        })
+        .unzip();
+
+    tokio::spawn(async move {
+        let mut stream = std::pin::pin!(stream);
+        loop {
+            // Once every choice stream is dropped, dropping the stream closes the connection,
+            // which aborts the generation.
+            let item = tokio::select! {
+                _ = all_closed(&senders) => break,
+                item = stream.next() => item,
+            };
+            let Some(item) = item else {
+                break;
+            };
+            match item {
+                // A dropped choice stream only means its items are not wanted.
+                Ok(item) => match item.index() {
+                    Some(index) => {
+                        if let Some(sender) = senders.get(index) {
+                            let _ = sender.send(Ok(item));
+                        }
+                    }
+                    None => {
+                        for sender in &senders {
+                            let _ = sender.send(Ok(item.clone()));
+                        }
+                    }
+                },
+                Err(err) => {
+                    let message = err.to_string();
+                    let mut err = Some(err);
+                    for sender in &senders {
+                        let err = err
+                            .take()
+                            .unwrap_or_else(|| Error::ResponseError(message.clone()));
+                        let _ = sender.send(Err(err));
+                    }
+                }
+            }
+            if senders.iter().all(UnboundedSender::is_closed) {
+                break;
+            }
+        }
+    });
+
+    streams
+}
+
+/// Waits until every choice stream is dropped.
+async fn all_closed(senders: &[UnboundedSender<Result<StreamItem>>]) {
+    futures::future::join_all(senders.iter().map(UnboundedSender::closed)).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    /// Sets a flag when the upstream stream is dropped.
+    struct DropFlag(Arc<AtomicBool>);
+
+    impl Drop for DropFlag {
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    #[tokio::test]
+    async fn upstream_is_dropped_with_every_choice_stream() {
+        let dropped = Arc::new(AtomicBool::new(false));
+        let polled = Arc::new(AtomicUsize::new(0));
+        let guard = DropFlag(dropped.clone());
+        let counter = polled.clone();
+        let upstream = futures::stream::unfold((guard, counter), |(guard, counter)| async move {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+            let index = counter.fetch_add(1, Ordering::SeqCst) % 2;
+            let item = StreamItem::Content {
+                index,
+                content: "token".to_string(),
+            };
+            Some((Ok(item), (guard, counter)))
+        });
+
+        let mut streams = demultiplex(upstream, 2);
+        let second = streams.pop().unwrap();
+        let mut first = streams.pop().unwrap();
+        assert!(first.next().await.unwrap().is_ok());
+        drop(second);
+        assert!(first.next().await.unwrap().is_ok());
+        assert!(!dropped.load(Ordering::SeqCst));
+
+        drop(first);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(dropped.load(Ordering::SeqCst));
+        let count = polled.load(Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(polled.load(Ordering::SeqCst), count);
+    }
+}
@@ -1,5 +1,9 @@
-use crate::types::Role;
+use crate::err::ApiError;
+use crate::types::{Model, Role, TokenUsage};
+use crate::Error;
+use reqwest::{header::HeaderMap, StatusCode};
 use serde::Deserialize;
+use std::str::FromStr;
 
 /// Represents a change in the content and role of a response.
 #[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Deserialize)]
@@ -8,6 +12,34 @@ pub struct Delta {
     pub role: Option<Role>,
     /// The content of the message, if changed.
     pub content: Option<String>,
+    /// The fragments of tool calls streamed in this chunk, if any.
+    #[serde(default)]
+    pub tool_calls: Option<Vec<ToolCallDelta>>,
+}
+
+/// Represents a fragment of a tool call streamed in a chunk.
+///
+/// The first fragment of a call carries its `id`, `type` and function name; subsequent fragments
+/// with the same `index` carry pieces of the JSON-encoded arguments to be concatenated.
+#[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Deserialize)]
+pub struct ToolCallDelta {
+    /// The index of the tool call within the message.
+    pub index: usize,
+    /// The unique identifier of the call, if this is its first fragment.
+    pub id: Option<String>,
+    /// The type of the tool, if this is the first fragment of the call.
+    pub r#type: Option<String>,
+    /// The fragment of the function call, if any.
+    pub function: Option<FunctionCallDelta>,
+}
+
+/// Represents a fragment of a function call streamed in a chunk.
+#[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Deserialize)]
+pub struct FunctionCallDelta {
+    /// The name of the function, if this is the first fragment of the call.
+    pub name: Option<String>,
+    /// The next piece of the JSON-encoded arguments, if any.
+    pub arguments: Option<String>,
 }
 
 /// Represents a choice in the chunk of a response.
@@ -31,7 +63,47 @@ pub struct Chunk {
     /// The timestamp of the creation of the chunk.
     pub created: u64,
     /// The model used to generate the chunk.
-    pub model: String,
+    pub model: Model,
     /// A vector of choices in the chunk.
     pub choices: Vec<ChunkChoice>,
+    /// The token usage of the whole request, only sent in the final chunk when requested with
+    /// `StreamOptions::include_usage`.
+    #[serde(default)]
+    pub usage: Option<TokenUsage>,
+}
+
+impl FromStr for Chunk {
+    type Err = Error;
+
+    /// Parses the data of a streamed event into a `Chunk`.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The data of the event.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `Chunk`, `Error::RequestFailed` if the API reported an error in the
+    /// middle of the stream, or `Error::UnexpectedChunk` if the data is not a chunk.
+    fn from_str(data: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(data).map_err(|source| {
+            let value = serde_json::from_str::<serde_json::Value>(data).ok();
+            if value
+                .as_ref()
+                .and_then(|value| value.get("error"))
+                .is_some()
+            {
+                Error::RequestFailed(Box::new(ApiError::new(
+                    StatusCode::OK,
+                    HeaderMap::new(),
+                    data.to_string(),
+                )))
+            } else {
+                Error::UnexpectedChunk {
+                    data: data.to_string(),
+                    source,
+                }
+            }
+        })
+    }
 }
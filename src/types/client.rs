@@ -1,33 +1,65 @@
+use crate::err::ApiError;
+use crate::types::response_format::strip_code_fences;
+use crate::types::{
+    ClientBuilder, Embedding, EmbeddingInput, EmbeddingResponse, ResponseFormat, RetryPolicy,
+    ToolRegistry, ToolRun,
+};
+use crate::Message;
 use crate::{ChatInput, Error, Response, Result};
+use serde::de::DeserializeOwned;
 use std::ops::{Deref, DerefMut};
 
 #[cfg(feature = "stream")]
-use crate::types::{Chunk, ChunkChoice, Delta, StreamItem};
+use crate::json_stream::{json_events, json_items, JsonEvent};
+#[cfg(feature = "stream")]
+use crate::segmenter::{IntoSegmenter, Passthrough, Segmenter};
+#[cfg(feature = "stream")]
+use crate::types::{
+    demultiplex, CancellableStream, ChoiceStream, Chunk, ResumePolicy, StreamAccumulator,
+    StreamItem, StreamTimeouts,
+};
 #[cfg(feature = "stream")]
 use eventsource_stream::{Event, Eventsource};
 #[cfg(feature = "stream")]
+use futures::future::Either;
+#[cfg(feature = "stream")]
 use futures::stream::{unfold, Stream, StreamExt};
+#[cfg(feature = "stream")]
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+#[cfg(feature = "stream")]
+use std::time::Duration;
 
 /// The main client structure to interact with the API.
 #[derive(Debug, Clone)]
-pub struct Client(reqwest::Client);
+pub struct Client {
+    pub(crate) inner: reqwest::Client,
+    pub(crate) base_url: String,
+    pub(crate) retry_policy: RetryPolicy,
+    #[cfg(feature = "stream")]
+    pub(crate) resume_policy: ResumePolicy,
+}
 
 impl Deref for Client {
     type Target = reqwest::Client;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.inner
     }
 }
 
 impl DerefMut for Client {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+        &mut self.inner
     }
 }
 
 impl Client {
-    const API_URL: &'static str = "https://api.openai.com/v1/chat/completions";
+    /// The base URL of the official OpenAI API.
+    pub const DEFAULT_BASE_URL: &'static str = "https://api.openai.com/v1";
+
+    /// The instructions added by `Client::completion_json` when JSON object mode is requested
+    /// and no message mentions JSON.
+    pub const JSON_OBJECT_PROMPT: &'static str = "Reply with a single JSON object.";
 
     /// Creates a new `Client` instance with the specified API key.
     ///
@@ -39,24 +71,48 @@ impl Client {
     ///
     /// A `Result` containing a new `Client` instance or an `Error`.
     pub fn new(api_key: String) -> Result<Self> {
-        let mut headers = reqwest::header::HeaderMap::new();
-        headers.insert(
-            "Authorization",
-            reqwest::header::HeaderValue::from_str(&format!("Bearer {}", api_key))?,
-        );
-        headers.insert(
-            reqwest::header::CONTENT_TYPE,
-            reqwest::header::HeaderValue::from_static("application/json"),
-        );
-
-        let client = reqwest::Client::builder()
-            .default_headers(headers)
-            .build()?;
-        Ok(Self(client))
+        Self::builder(api_key).build()
+    }
+
+    /// Creates a `ClientBuilder` for configuring a `Client` with the specified API key.
+    ///
+    /// # Arguments
+    ///
+    /// * `api_key` - The API key used for the `Authorization` header.
+    ///
+    /// # Returns
+    ///
+    /// A new `ClientBuilder` instance.
+    pub fn builder(api_key: impl Into<String>) -> ClientBuilder {
+        ClientBuilder::new(api_key)
+    }
+
+    /// Returns the base URL that endpoint paths are appended to.
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Builds the full URL of an endpoint from the configured base URL.
+    fn endpoint(&self, path: &str) -> String {
+        format!("{}/{}", self.base_url, path.trim_start_matches('/'))
+    }
+
+    /// Returns the policy used to retry failed requests.
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry_policy
+    }
+
+    /// Returns the policy used to resume interrupted streams.
+    #[cfg(feature = "stream")]
+    pub fn resume_policy(&self) -> &ResumePolicy {
+        &self.resume_policy
     }
 
     /// Sends the provided `ChatInput` to the API and returns the `reqwest::Response`.
     ///
+    /// The input is validated before it is sent, and transient failures are retried according
+    /// to the configured `RetryPolicy`.
+    ///
     /// # Arguments
     ///
     /// * `input` - A reference to a `ChatInput` instance.
@@ -64,16 +120,54 @@ impl Client {
     /// # Returns
     ///
     /// A `Result` containing a `reqwest::Response` instance or an `Error`.
-    pub async fn send(&self, input: &ChatInput<'_>) -> Result<reqwest::Response> {
-        let response = self.post(Self::API_URL).body(input).send().await?;
+    pub async fn send(&self, input: &ChatInput) -> Result<reqwest::Response> {
+        input.validate()?;
+        self.execute(|| self.post(self.endpoint("chat/completions")).body(input))
+            .await
+    }
+
+    /// Sends the request produced by `request`, retrying transient failures.
+    ///
+    /// If more than one attempt was made, the final error is wrapped in
+    /// `Error::RetriesExhausted` along with the number of attempts.
+    async fn execute(
+        &self,
+        request: impl Fn() -> reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response> {
+        let mut attempt = 1;
+        loop {
+            match Self::send_once(request()).await {
+                Ok(response) => return Ok(response),
+                Err(err)
+                    if attempt < self.retry_policy.max_attempts
+                        && self.retry_policy.is_retryable(&err) =>
+                {
+                    tokio::time::sleep(self.retry_policy.delay(attempt, &err)).await;
+                    attempt += 1;
+                }
+                Err(err) if attempt > 1 => {
+                    return Err(Error::RetriesExhausted {
+                        attempts: attempt,
+                        source: Box::new(err),
+                    })
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Sends a single request and turns non-success statuses into `Error::RequestFailed`.
+    async fn send_once(request: reqwest::RequestBuilder) -> Result<reqwest::Response> {
+        let response = request.send().await?;
         let status = response.status();
         if status.is_success() {
             Ok(response)
         } else {
-            let status_code = response.status();
             let headers = response.headers().to_owned();
             let body = response.text().await?;
-            Err(Error::RequestFailed(status_code, headers, body))
+            Err(Error::RequestFailed(Box::new(ApiError::new(
+                status, headers, body,
+            ))))
         }
     }
 
@@ -86,12 +180,280 @@ impl Client {
     /// # Returns
     ///
     /// A `Result` containing a `Response` instance or an `Error`.
-    pub async fn completion<'a>(&self, input: &ChatInput<'a>) -> Result<Response> {
+    pub async fn completion(&self, input: &ChatInput) -> Result<Response> {
         Ok(self.send(input).await?.json::<Response>().await?)
     }
 
+    /// Sends a completion request and deserializes the content of the first choice into `T`.
+    ///
+    /// If `input.response_format` is not set, JSON object mode is requested. As the API rejects
+    /// this mode unless a message mentions JSON, `Client::JSON_OBJECT_PROMPT` is then added as a
+    /// system message if none does. Markdown code fences around the output are removed before
+    /// parsing. When parsing fails, the model is asked up to `max_repairs` times to correct its
+    /// output, with the parse error appended to the messages.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - A reference to a `ChatInput` instance.
+    /// * `max_repairs` - The maximum number of follow-up requests made to fix unparsable output.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the deserialized value or an `Error`, which is
+    /// `Error::InvalidJsonOutput` if the output could not be parsed.
+    pub async fn completion_json<T: DeserializeOwned>(
+        &self,
+        input: &ChatInput,
+        max_repairs: u32,
+    ) -> Result<T> {
+        let mut input = input.clone();
+        if input.response_format.is_none() {
+            input.response_format = Some(ResponseFormat::JsonObject);
+            let mentions_json = input
+                .messages
+                .iter()
+                .any(|message| message.content.to_lowercase().contains("json"));
+            if !mentions_json {
+                let prompt = Message::system(Self::JSON_OBJECT_PROMPT.to_string());
+                input.messages.insert(0, prompt);
+            }
+        }
+
+        let mut repairs = 0;
+        loop {
+            let response = self.completion(&input).await?;
+            let content = response
+                .choices
+                .into_iter()
+                .next()
+                .ok_or_else(|| Error::ResponseError("There is no choice in response".to_string()))?
+                .message
+                .content;
+
+            match serde_json::from_str::<T>(strip_code_fences(&content)) {
+                Ok(value) => return Ok(value),
+                Err(err) if repairs < max_repairs => {
+                    repairs += 1;
+                    input.messages.push(Message::assistant(content));
+                    input.messages.push(Message::user(format!(
+                        "Your previous response could not be parsed: {err}. \
+                         Reply again with only valid JSON in the expected format."
+                    )));
+                }
+                Err(source) => return Err(Error::InvalidJsonOutput { content, source }),
+            }
+        }
+    }
+
+    /// Computes the embeddings of the input texts.
+    ///
+    /// Inputs with more than `EmbeddingInput::MAX_BATCH_SIZE` texts are split into batches sent
+    /// concurrently, as with `embeddings_batched`.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - A reference to an `EmbeddingInput` instance.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing an `EmbeddingResponse` instance or an `Error`.
+    pub async fn embeddings(&self, input: &EmbeddingInput) -> Result<EmbeddingResponse> {
+        self.embeddings_batched(input, EmbeddingInput::MAX_BATCH_SIZE, 4)
+            .await
+    }
+
+    /// Computes the embeddings of the input texts, sending them in batches.
+    ///
+    /// The responses of the batches are merged into one, with the embeddings indexed by the
+    /// position of their text in `input` and the usage summed.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - A reference to an `EmbeddingInput` instance.
+    /// * `batch_size` - The maximum number of texts per request, capped at
+    ///   `EmbeddingInput::MAX_BATCH_SIZE`.
+    /// * `concurrency` - The maximum number of requests in flight.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing an `EmbeddingResponse` instance or the `Error` of the first batch
+    /// that failed.
+    pub async fn embeddings_batched(
+        &self,
+        input: &EmbeddingInput,
+        batch_size: usize,
+        concurrency: usize,
+    ) -> Result<EmbeddingResponse> {
+        let batch_size = batch_size.clamp(1, EmbeddingInput::MAX_BATCH_SIZE);
+        if input.input.len() <= batch_size {
+            return self.embeddings_once(input).await;
+        }
+
+        let mut batches = input
+            .input
+            .chunks(batch_size)
+            .enumerate()
+            .map(|(number, texts)| {
+                let batch = EmbeddingInput {
+                    model: input.model.clone(),
+                    input: texts.to_vec(),
+                    dimensions: input.dimensions,
+                    encoding_format: input.encoding_format,
+                    user: input.user.clone(),
+                };
+                (number * batch_size, batch)
+            });
+        let mut tasks = tokio::task::JoinSet::new();
+        let mut responses = Vec::new();
+        loop {
+            while tasks.len() < concurrency.max(1) {
+                let Some((offset, batch)) = batches.next() else {
+                    break;
+                };
+                let client = self.clone();
+                tasks.spawn(async move {
+                    let response = client.embeddings_once(&batch).await?;
+                    Ok::<_, Error>((offset, response))
+                });
+            }
+            match tasks.join_next().await {
+                Some(result) => {
+                    responses.push(result.map_err(|err| Error::ResponseError(err.to_string()))??)
+                }
+                None => break,
+            }
+        }
+
+        responses.sort_by_key(|(offset, _)| *offset);
+        let mut responses = responses.into_iter();
+        let (_, mut merged) = responses
+            .next()
+            .ok_or_else(|| Error::ResponseError("There is no embedding in response".to_string()))?;
+        for (offset, response) in responses {
+            merged.usage += response.usage;
+            merged
+                .data
+                .extend(response.data.into_iter().map(|embedding| Embedding {
+                    index: embedding.index + offset,
+                    ..embedding
+                }));
+        }
+        Ok(merged)
+    }
+
+    /// Sends a single embeddings request.
+    async fn embeddings_once(&self, input: &EmbeddingInput) -> Result<EmbeddingResponse> {
+        let mut response = self
+            .execute(|| self.post(self.endpoint("embeddings")).json(input))
+            .await?
+            .json::<EmbeddingResponse>()
+            .await?;
+        response.data.sort_by_key(|embedding| embedding.index);
+        Ok(response)
+    }
+
+    /// Sends completion requests until the model returns a final answer, executing the tool calls
+    /// it makes in between with the functions of the `registry`.
+    ///
+    /// If `input.tools` is not set, the definitions of the registered tools are sent. Independent
+    /// tool calls made in the same response are executed concurrently.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - A reference to a `ChatInput` instance.
+    /// * `registry` - A reference to the `ToolRegistry` holding the functions to call.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `ToolRun` with the final response and the resulting conversation,
+    /// or an `Error`.
+    pub async fn run_with_tools(
+        &self,
+        input: &ChatInput,
+        registry: &ToolRegistry,
+    ) -> Result<ToolRun> {
+        let mut input = input.clone();
+        if input.tools.is_none() {
+            input.tools = Some(registry.definitions());
+        }
+
+        for _ in 0..registry.max_iterations {
+            let response = self.completion(&input).await?;
+            let message = &response
+                .choices
+                .first()
+                .ok_or_else(|| Error::ResponseError("There is no choice in response".to_string()))?
+                .message;
+
+            match &message.tool_calls {
+                Some(tool_calls) if !tool_calls.is_empty() => {
+                    let results = registry.call_all(tool_calls).await?;
+                    input.messages.push(message.clone());
+                    input.messages.extend(results);
+                }
+                _ => {
+                    input.messages.push(message.clone());
+                    return Ok(ToolRun {
+                        response,
+                        messages: input.messages,
+                    });
+                }
+            }
+        }
+        Err(Error::ToolIterationsExceeded(registry.max_iterations))
+    }
+
+    /// Stream API yielding the raw `Chunk`s of the response.
+    ///
+    /// A stream that ends before every choice received a finish reason, or that is cut before
+    /// any choice finished and without the `[DONE]` sentinel, yields `Error::IncompleteStream`
+    /// last.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - A reference to a `ChatInput` instance.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a stream of `Chunk` instances or an `Error`.
+    #[cfg(feature = "stream")]
+    pub async fn chunks(&self, input: &ChatInput) -> Result<impl Stream<Item = Result<Chunk>>> {
+        let stream = self.send(input).await?.bytes_stream().eventsource();
+        let state = (stream, BTreeSet::new(), BTreeSet::new());
+        Ok(unfold(Some(state), |state| async move {
+            let (mut stream, mut started, mut finished) = state?;
+            loop {
+                let done = match stream.next().await {
+                    Some(Ok(Event { data, .. })) if data == "[DONE]" => true,
+                    Some(Ok(Event { data, .. })) if data.trim().is_empty() => continue,
+                    Some(Ok(Event { data, .. })) => {
+                        let chunk = data.parse::<Chunk>();
+                        for choice in chunk.iter().flat_map(|chunk| &chunk.choices) {
+                            started.insert(choice.index);
+                            if choice.finish_reason.is_some() {
+                                finished.insert(choice.index);
+                            }
+                        }
+                        return Some((chunk, Some((stream, started, finished))));
+                    }
+                    // The connection cannot be resumed after a transport error.
+                    Some(Err(err)) => return Some((Err(err.into()), None)),
+                    None => false,
+                };
+                return incomplete(&started, &finished, done).map(|err| (Err(err), None));
+            }
+        }))
+    }
+
     /// Stream API for processing a large input in chunks.
     ///
+    /// Transport and SSE errors are yielded as `Error::EventStreamError` and end the stream. A
+    /// stream that finished cleanly ends with `StreamItem::Done`, while a stream that ends before
+    /// every choice received a finish reason yields `Error::IncompleteStream` instead.
+    ///
+    /// Interrupted responses are resumed according to the configured `ResumePolicy`, in which
+    /// case these errors are only yielded once the resumes are exhausted.
+    ///
     /// # Arguments
     ///
     /// * `input` - A reference to a `ChatInput` instance.
@@ -102,117 +464,567 @@ impl Client {
     #[cfg(feature = "stream")]
     pub async fn stream(
         &self,
-        input: &ChatInput<'_>,
+        input: &ChatInput,
     ) -> Result<impl Stream<Item = Result<StreamItem>>> {
-        let stream = self.send(input).await?.bytes_stream().eventsource();
-        Ok(unfold(stream, move |mut stream| async move {
-            while let Some(Ok(Event { data, .. })) = stream.next().await {
-                if data == "[DONE]" {
-                    continue;
-                }
-                match serde_json::from_str::<crate::types::Chunk>(&data) {
-                    Ok(chunk) => return Some((Ok(chunk.into()), stream)),
-                    Err(err) => return Some((Err(err.into()), stream)),
+        let stream = self.items(input, StreamState::new(Passthrough)).await?;
+        if self.resume_policy.max_resumes == 0 || input.n.unwrap_or(1) > 1 {
+            return Ok(Either::Left(stream));
+        }
+        let resume = Resume {
+            client: self.clone(),
+            input: input.clone(),
+            partial: String::new(),
+            started: false,
+            resumable: true,
+            resumes: 0,
+        };
+
+        Ok(Either::Right(unfold(
+            (stream.boxed(), resume),
+            |(mut stream, mut resume)| async move {
+                loop {
+                    match stream.next().await? {
+                        // The continuation starts a new message, which is stitched to the first.
+                        Ok(StreamItem::Start { .. }) if resume.started => continue,
+                        Ok(item) => {
+                            match &item {
+                                StreamItem::Start { .. } => resume.started = true,
+                                StreamItem::Content { content, .. } => {
+                                    resume.partial.push_str(content)
+                                }
+                                StreamItem::ToolCalls { .. } | StreamItem::FinishReason { .. } => {
+                                    resume.resumable = false
+                                }
+                                _ => {}
+                            }
+                            return Some((Ok(item), (stream, resume)));
+                        }
+                        Err(err) if resume.should_resume(&err) => {
+                            resume.resumes += 1;
+                            // Without any content, there is nothing to continue from.
+                            let input = if resume.partial.is_empty() {
+                                resume.input.clone()
+                            } else {
+                                let policy = &resume.client.resume_policy;
+                                policy.continuation(&resume.input, &resume.partial)
+                            };
+                            match resume
+                                .client
+                                .items(&input, StreamState::new(Passthrough))
+                                .await
+                            {
+                                Ok(continuation) => stream = continuation.boxed(),
+                                Err(err) => {
+                                    resume.resumable = false;
+                                    return Some((Err(err), (stream, resume)));
+                                }
+                            }
+                        }
+                        Err(err) => return Some((Err(err), (stream, resume))),
+                    }
                 }
+            },
+        )))
+    }
+
+    /// Streams a response that can be cancelled and is subject to timeouts.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - A reference to a `ChatInput` instance.
+    /// * `timeouts` - The total and idle timeouts enforced on the stream, which start when the
+    ///   response headers are received.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `CancellableStream` or an `Error`.
+    #[cfg(feature = "stream")]
+    pub async fn stream_cancellable(
+        &self,
+        input: &ChatInput,
+        timeouts: StreamTimeouts,
+    ) -> Result<CancellableStream> {
+        Ok(CancellableStream::new(self.stream(input).await?, timeouts))
+    }
+
+    /// Streams a response with several choices as one stream per choice.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - A reference to a `ChatInput` instance, whose `n` sets the number of choices.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `ChoiceStream`s, ordered by choice index, or an `Error`.
+    #[cfg(feature = "stream")]
+    pub async fn stream_choices(&self, input: &ChatInput) -> Result<Vec<ChoiceStream>> {
+        let stream = self.stream(input).await?;
+        Ok(demultiplex(stream, input.n.unwrap_or(1).into()))
+    }
+
+    /// Streams a response while reassembling it into the `Response` a non-streaming call would
+    /// have returned.
+    ///
+    /// A response cut before it finished fails with `Error::IncompleteStream` rather than being
+    /// returned truncated.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - A reference to a `ChatInput` instance.
+    /// * `on_chunk` - A callback observing each `Chunk` as it arrives.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the assembled `Response` or an `Error`.
+    #[cfg(feature = "stream")]
+    pub async fn stream_and_collect(
+        &self,
+        input: &ChatInput,
+        mut on_chunk: impl FnMut(&Chunk),
+    ) -> Result<Response> {
+        let mut accumulator = StreamAccumulator::new();
+        let mut chunks = std::pin::pin!(self.chunks(input).await?);
+        while let Some(chunk) = chunks.next().await {
+            let chunk = chunk?;
+            on_chunk(&chunk);
+            accumulator.push(&chunk);
+        }
+        accumulator.finish()
+    }
+
+    /// Streams a response in JSON mode, reporting the values of the first choice as they complete.
+    ///
+    /// If `input.response_format` is not set, JSON object mode is requested.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - A reference to a `ChatInput` instance.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a stream of `JsonEvent`s, ending with `JsonEvent::Complete`, or an
+    /// `Error`.
+    #[cfg(feature = "stream")]
+    pub async fn stream_json(
+        &self,
+        input: &ChatInput,
+    ) -> Result<impl Stream<Item = Result<JsonEvent>>> {
+        let mut input = input.clone();
+        if input.response_format.is_none() {
+            input.response_format = Some(ResponseFormat::JsonObject);
+            let mentions_json = input
+                .messages
+                .iter()
+                .any(|message| message.content.to_lowercase().contains("json"));
+            if !mentions_json {
+                let prompt = Message::system(Self::JSON_OBJECT_PROMPT.to_string());
+                input.messages.insert(0, prompt);
             }
-            None
-        }))
+        }
+        let stream = self.stream(&input).await?.filter(|item| {
+            let first = !matches!(item, Ok(item) if item.index().is_some_and(|index| index > 0));
+            async move { first }
+        });
+        Ok(json_events(stream))
+    }
+
+    /// Streams a response in JSON mode, deserializing the elements of an array into `T` as they
+    /// complete.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - A reference to a `ChatInput` instance.
+    /// * `pointer` - The JSON Pointer of the array, such as `/items`, or `""` for a root array.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a stream of the deserialized elements or an `Error`.
+    #[cfg(feature = "stream")]
+    pub async fn stream_json_items<T: DeserializeOwned>(
+        &self,
+        input: &ChatInput,
+        pointer: &str,
+    ) -> Result<impl Stream<Item = Result<T>>> {
+        Ok(json_items(self.stream_json(input).await?, pointer))
     }
 
-    /// Compresses and processes the response stream using the specified delimiter.
+    /// Compresses and processes the response stream into segments, such as sentences or lines.
+    ///
+    /// The content of each choice is split by its own clone of the segmenter and emitted as one
+    /// `StreamItem::Content` per segment, so responses with several choices can be split with
+    /// `demultiplex`. The rest of the content of a choice is emitted before its
+    /// `StreamItem::FinishReason`.
     ///
     /// # Arguments
     ///
     /// * `input` - A reference to a `ChatInput` instance.
-    /// * `delimiter` - A string slice that holds the delimiter.
+    /// * `segmenter` - The `Segmenter` splitting the content, such as `Sentences::new()`, or a
+    ///   delimiter such as `"\n"`.
     ///
     /// # Returns
     ///
     /// A `Result` containing a stream of `StreamItem` instances or an `Error`.
     #[cfg(feature = "stream")]
-    pub async fn compress(
+    pub async fn compress<S: IntoSegmenter>(
         &self,
-        input: &ChatInput<'_>,
-        delimiter: &str,
+        input: &ChatInput,
+        segmenter: S,
+    ) -> Result<impl Stream<Item = Result<StreamItem>>> {
+        self.items(input, StreamState::new(segmenter.into_segmenter()))
+            .await
+    }
+
+    /// Streams the `StreamItem`s of a response through the given state.
+    #[cfg(feature = "stream")]
+    async fn items<S: Segmenter>(
+        &self,
+        input: &ChatInput,
+        state: StreamState<S>,
     ) -> Result<impl Stream<Item = Result<StreamItem>>> {
         let stream = self.send(input).await?.bytes_stream().eventsource();
 
         Ok(unfold(
-            (stream, String::new(), delimiter.to_string()),
-            move |(mut stream, mut buffer, delimiter)| async move {
-                async fn process_event(
-                    result: std::result::Result<
-                        Event,
-                        eventsource_stream::EventStreamError<reqwest::Error>,
-                    >,
-                    buffer: &mut String,
-                    delimiter: &str,
-                ) -> Result<Option<StreamItem>> {
-                    let Event { data, .. } = result.map_err(Error::from)?;
-
-                    if data == "[DONE]" {
-                        return Ok(Some(StreamItem::FinishReason(buffer.to_owned())));
+            (stream, state),
+            |(mut stream, mut state)| async move {
+                loop {
+                    if let Some(item) = state.pending.pop_front() {
+                        return Some((item, (stream, state)));
                     }
-
-                    let Chunk {
-                        id,
-                        object,
-                        created,
-                        model,
-                        mut choices,
-                    } = serde_json::from_str(&data).map_err(Error::from)?;
-
-                    match choices.pop() {
-                        Some(ChunkChoice {
-                            delta: Delta { content, role },
-                            finish_reason,
-                            ..
-                        }) => {
-                            if let Some(mut reason) = finish_reason {
-                                std::mem::swap(&mut reason, buffer);
-                                Ok(Some(StreamItem::Content(reason)))
-                            } else if let Some(content) = content {
-                                buffer.push_str(&content);
-                                if let Some(index) = buffer.find(delimiter) {
-                                    let content_before_delimiter =
-                                        buffer.drain(..index).collect::<String>();
-                                    buffer.drain(..delimiter.len());
-
-                                    Ok(Some(StreamItem::Content(content_before_delimiter)))
-                                } else {
-                                    Ok(None)
-                                }
-                            } else if let Some(role) = role {
-                                Ok(Some(StreamItem::Start {
-                                    id,
-                                    object,
-                                    created,
-                                    model,
-                                    role,
-                                }))
-                            } else {
-                                Err(Error::ResponseError(
-                                    "There is no content or role in response".to_string(),
-                                ))
+                    if state.done {
+                        return None;
+                    }
+                    let event = match state.idle_timeout() {
+                        Some(timeout) => match tokio::time::timeout(timeout, stream.next()).await {
+                            Ok(event) => event,
+                            Err(_) => {
+                                state.flush_idle();
+                                continue;
                             }
+                        },
+                        None => stream.next().await,
+                    };
+                    match event {
+                        Some(Ok(Event { data, .. })) if data == "[DONE]" => state.finish(true),
+                        Some(Ok(Event { data, .. })) if data.trim().is_empty() => {}
+                        Some(Ok(Event { data, .. })) => match data.parse::<Chunk>() {
+                            Ok(chunk) => state.push(chunk),
+                            Err(err) => state.pending.push_back(Err(err)),
+                        },
+                        Some(Err(err)) => {
+                            state.pending.push_back(Err(err.into()));
+                            state.done = true;
                         }
-                        None => Err(Error::ResponseError(
-                            "There is no choice in response".to_string(),
-                        )),
+                        None => state.finish(false),
                     }
                 }
+            },
+        ))
+    }
+}
 
-                while let Some(result) = stream.next().await {
-                    match process_event(result, &mut buffer, &delimiter).await {
-                        Ok(Some(stream_item)) => {
-                            return Some((Ok(stream_item), (stream, buffer, delimiter)));
-                        }
-                        Ok(None) => continue,
-                        Err(err) => return Some((Err(err), (stream, buffer, delimiter))),
+/// The state of `Client::stream` for resuming an interrupted response.
+#[cfg(feature = "stream")]
+struct Resume {
+    client: Client,
+    input: ChatInput,
+    partial: String,
+    started: bool,
+    resumable: bool,
+    resumes: u32,
+}
+
+#[cfg(feature = "stream")]
+impl Resume {
+    /// Determines whether the response is resumed after the given error.
+    fn should_resume(&self, error: &Error) -> bool {
+        let policy = &self.client.resume_policy;
+        self.resumable && self.resumes < policy.max_resumes && policy.is_resumable(error)
+    }
+}
+
+/// The state of a stream of `StreamItem`s, tracking the choices until they finish and splitting
+/// their content with a clone of the segmenter each.
+#[cfg(feature = "stream")]
+struct StreamState<S> {
+    segmenter: S,
+    segmenters: BTreeMap<usize, S>,
+    started: BTreeSet<usize>,
+    finished: BTreeSet<usize>,
+    pending: VecDeque<Result<StreamItem>>,
+    done: bool,
+}
+
+#[cfg(feature = "stream")]
+impl<S: Segmenter> StreamState<S> {
+    fn new(segmenter: S) -> Self {
+        Self {
+            segmenter,
+            segmenters: BTreeMap::new(),
+            started: BTreeSet::new(),
+            finished: BTreeSet::new(),
+            pending: VecDeque::new(),
+            done: false,
+        }
+    }
+
+    /// Queues the items of a chunk, splitting the content of each choice into segments.
+    fn push(&mut self, chunk: Chunk) {
+        for item in StreamItem::from_chunk(chunk) {
+            if let Some(index) = item.index() {
+                match item {
+                    StreamItem::FinishReason { .. } => self.finished.insert(index),
+                    _ => self.started.insert(index),
+                };
+            }
+            match item {
+                StreamItem::Content { index, content } => {
+                    let segmenter = self
+                        .segmenters
+                        .entry(index)
+                        .or_insert_with(|| self.segmenter.clone());
+                    for content in segmenter.push(&content) {
+                        self.pending
+                            .push_back(Ok(StreamItem::Content { index, content }));
                     }
                 }
-                None
-            },
-        ))
+                StreamItem::FinishReason { index, reason } => {
+                    self.flush(index);
+                    self.pending
+                        .push_back(Ok(StreamItem::FinishReason { index, reason }));
+                }
+                item => self.pending.push_back(Ok(item)),
+            }
+        }
+    }
+
+    /// Returns how long to wait for the next event before flushing, if any content is buffered.
+    fn idle_timeout(&self) -> Option<Duration> {
+        self.segmenter
+            .idle_timeout()
+            .filter(|_| !self.segmenters.is_empty())
+    }
+
+    /// Queues the content buffered for every choice, which keeps streaming.
+    fn flush_idle(&mut self) {
+        for (&index, segmenter) in &mut self.segmenters {
+            if let Some(content) = segmenter.flush() {
+                self.pending
+                    .push_back(Ok(StreamItem::Content { index, content }));
+            }
+        }
+    }
+
+    /// Queues the content buffered for a choice that finished.
+    fn flush(&mut self, index: usize) {
+        if let Some(content) = self
+            .segmenters
+            .remove(&index)
+            .and_then(|mut segmenter| segmenter.flush())
+        {
+            self.pending
+                .push_back(Ok(StreamItem::Content { index, content }));
+        }
+    }
+
+    /// Queues the remaining content of every choice and ends the stream.
+    ///
+    /// # Arguments
+    ///
+    /// * `done` - Whether the `[DONE]` sentinel was received.
+    fn finish(&mut self, done: bool) {
+        let indices = self.segmenters.keys().copied().collect::<Vec<_>>();
+        for index in indices {
+            self.flush(index);
+        }
+        if let Some(err) = incomplete(&self.started, &self.finished, done) {
+            self.pending.push_back(Err(err));
+        } else if done {
+            self.pending.push_back(Ok(StreamItem::Done));
+        }
+        self.done = true;
+    }
+}
+
+/// Builds the error reporting a stream that ended before its response was complete, if any.
+///
+/// # Arguments
+///
+/// * `started` - The indices of the choices that received any item.
+/// * `finished` - The indices of the choices that received a finish reason.
+/// * `done` - Whether the `[DONE]` sentinel was received.
+#[cfg(feature = "stream")]
+fn incomplete(started: &BTreeSet<usize>, finished: &BTreeSet<usize>, done: bool) -> Option<Error> {
+    let unfinished = started.difference(finished).copied().collect::<Vec<_>>();
+    // A stream cut before any choice arrived is only complete if the server said so.
+    (!unfinished.is_empty() || (!done && finished.is_empty()))
+        .then_some(Error::IncompleteStream { unfinished })
+}
+
+#[cfg(all(test, feature = "stream"))]
+mod tests {
+    use super::*;
+    use crate::Model;
+    use serde_json::{json, Value};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    /// Builds a server-sent event carrying a chunk of the first choice.
+    fn chunk(delta: Value, finish_reason: Option<&str>) -> String {
+        let chunk = json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion.chunk",
+            "created": 0,
+            "model": "gpt-4",
+            "choices": [{"index": 0, "delta": delta, "finish_reason": finish_reason}],
+        });
+        format!("data: {chunk}\n\n")
+    }
+
+    /// Reads a request and returns its JSON body.
+    async fn read_request(socket: &mut TcpStream) -> Value {
+        let mut request = Vec::new();
+        let mut buffer = [0; 4096];
+        loop {
+            let read = socket.read(&mut buffer).await.unwrap();
+            request.extend_from_slice(&buffer[..read]);
+            let text = String::from_utf8_lossy(&request);
+            if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                let length = head
+                    .lines()
+                    .find_map(|line| {
+                        let (name, value) = line.split_once(':')?;
+                        name.eq_ignore_ascii_case("content-length")
+                            .then(|| value.trim().parse::<usize>().unwrap())
+                    })
+                    .unwrap_or(0);
+                if body.len() >= length {
+                    return serde_json::from_str(body).unwrap();
+                }
+            }
+        }
+    }
+
+    /// Serves the given event streams, one per request, closing each connection after its
+    /// events, and returns the client with the given resume policy and the request bodies.
+    async fn serve(
+        responses: Vec<Vec<String>>,
+        resume_policy: ResumePolicy,
+    ) -> (Client, tokio::task::JoinHandle<Vec<Value>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let mut requests = Vec::new();
+            for events in responses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                requests.push(read_request(&mut socket).await);
+                let head = "HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\n\
+                    connection: close\r\n\r\n";
+                socket.write_all(head.as_bytes()).await.unwrap();
+                socket.write_all(events.concat().as_bytes()).await.unwrap();
+                socket.shutdown().await.unwrap();
+            }
+            requests
+        });
+        let client = Client::builder("key")
+            .base_url(format!("http://{address}"))
+            .resume_policy(resume_policy)
+            .build()
+            .unwrap();
+        (client, server)
+    }
+
+    fn input() -> ChatInput {
+        ChatInput::builder()
+            .model(Model::Gpt4)
+            .user("Say hello.")
+            .build()
+    }
+
+    #[tokio::test]
+    async fn stream_resumes_interrupted_response() {
+        let (client, server) = serve(
+            vec![
+                vec![chunk(json!({"role": "assistant", "content": "Hel"}), None)],
+                vec![
+                    chunk(json!({"role": "assistant", "content": "lo"}), None),
+                    chunk(json!({}), Some("stop")),
+                    "data: [DONE]\n\n".to_string(),
+                ],
+            ],
+            ResumePolicy::default(),
+        )
+        .await;
+
+        let items: Vec<StreamItem> = client
+            .stream(&input())
+            .await
+            .unwrap()
+            .map(Result::unwrap)
+            .collect()
+            .await;
+        let starts = items
+            .iter()
+            .filter(|item| matches!(item, StreamItem::Start { .. }))
+            .count();
+        let content: String = items
+            .iter()
+            .filter_map(|item| match item {
+                StreamItem::Content { content, .. } => Some(content.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(starts, 1);
+        assert_eq!(content, "Hello");
+        assert!(matches!(items.last(), Some(StreamItem::Done)));
+
+        let requests = server.await.unwrap();
+        let messages = requests[1]["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[1]["role"], "assistant");
+        assert_eq!(messages[1]["content"], "Hel");
+        assert_eq!(messages[2]["content"], ResumePolicy::DEFAULT_PROMPT);
+    }
+
+    #[tokio::test]
+    async fn stream_resends_request_without_content() {
+        let (client, server) = serve(
+            vec![
+                Vec::new(),
+                vec![
+                    chunk(
+                        json!({"role": "assistant", "content": "Hello"}),
+                        Some("stop"),
+                    ),
+                    "data: [DONE]\n\n".to_string(),
+                ],
+            ],
+            ResumePolicy::default(),
+        )
+        .await;
+
+        let items: Vec<Result<StreamItem>> = client.stream(&input()).await.unwrap().collect().await;
+        assert!(items.iter().all(Result::is_ok));
+
+        let requests = server.await.unwrap();
+        assert_eq!(requests[0], requests[1]);
+    }
+
+    #[tokio::test]
+    async fn stream_is_not_resumed_without_policy() {
+        let (client, server) = serve(
+            vec![vec![chunk(
+                json!({"role": "assistant", "content": "Hel"}),
+                None,
+            )]],
+            ResumePolicy::none(),
+        )
+        .await;
+
+        let items: Vec<Result<StreamItem>> = client.stream(&input()).await.unwrap().collect().await;
+        assert!(matches!(
+            items.last(),
+            Some(Err(Error::IncompleteStream { .. }))
+        ));
+        assert_eq!(server.await.unwrap().len(), 1);
     }
 }
@@ -0,0 +1,164 @@
+#[cfg(feature = "stream")]
+use crate::types::ResumePolicy;
+use crate::types::RetryPolicy;
+use crate::{Client, Result};
+use reqwest::header::{HeaderMap, HeaderValue, IntoHeaderName, AUTHORIZATION, CONTENT_TYPE};
+use std::time::Duration;
+
+/// A builder for configuring and creating a `Client`.
+///
+/// The builder allows pointing the client at any OpenAI-compatible server and
+/// customizing the underlying HTTP client.
+#[derive(Debug)]
+pub struct ClientBuilder {
+    api_key: String,
+    base_url: String,
+    organization: Option<String>,
+    project: Option<String>,
+    headers: HeaderMap,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    proxy: Option<reqwest::Proxy>,
+    user_agent: Option<String>,
+    retry_policy: RetryPolicy,
+    #[cfg(feature = "stream")]
+    resume_policy: ResumePolicy,
+}
+
+impl ClientBuilder {
+    /// Creates a new `ClientBuilder` with the specified API key and default settings.
+    ///
+    /// # Arguments
+    ///
+    /// * `api_key` - The API key used for the `Authorization` header.
+    ///
+    /// # Returns
+    ///
+    /// A new `ClientBuilder` instance.
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self {
+            api_key: api_key.into(),
+            base_url: Client::DEFAULT_BASE_URL.to_string(),
+            organization: None,
+            project: None,
+            headers: HeaderMap::new(),
+            timeout: None,
+            connect_timeout: None,
+            proxy: None,
+            user_agent: None,
+            retry_policy: RetryPolicy::none(),
+            #[cfg(feature = "stream")]
+            resume_policy: ResumePolicy::none(),
+        }
+    }
+
+    /// Sets the base URL that endpoint paths are appended to, e.g. `http://localhost:8080/v1`.
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    /// Sets the `OpenAI-Organization` header.
+    pub fn organization(mut self, organization: impl Into<String>) -> Self {
+        self.organization = Some(organization.into());
+        self
+    }
+
+    /// Sets the `OpenAI-Project` header.
+    pub fn project(mut self, project: impl Into<String>) -> Self {
+        self.project = Some(project.into());
+        self
+    }
+
+    /// Adds a header that is sent with every request.
+    pub fn header<K: IntoHeaderName>(mut self, key: K, value: HeaderValue) -> Self {
+        self.headers.insert(key, value);
+        self
+    }
+
+    /// Adds all of the given headers to the headers sent with every request.
+    pub fn headers(mut self, headers: HeaderMap) -> Self {
+        self.headers.extend(headers);
+        self
+    }
+
+    /// Sets the total timeout for each request.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the timeout for establishing a connection.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Routes all requests through the given proxy.
+    pub fn proxy(mut self, proxy: reqwest::Proxy) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    /// Sets the `User-Agent` header.
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Sets the policy used to retry transient failures. Requests are not retried by default.
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Sets the policy used to resume interrupted streams. Streams are not resumed by default.
+    #[cfg(feature = "stream")]
+    pub fn resume_policy(mut self, resume_policy: ResumePolicy) -> Self {
+        self.resume_policy = resume_policy;
+        self
+    }
+
+    /// Builds the `Client` with the configured settings.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a new `Client` instance or an `Error`.
+    pub fn build(self) -> Result<Client> {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            AUTHORIZATION,
+            HeaderValue::from_str(&format!("Bearer {}", self.api_key))?,
+        );
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        if let Some(organization) = &self.organization {
+            headers.insert("OpenAI-Organization", HeaderValue::from_str(organization)?);
+        }
+        if let Some(project) = &self.project {
+            headers.insert("OpenAI-Project", HeaderValue::from_str(project)?);
+        }
+        headers.extend(self.headers);
+
+        let mut builder = reqwest::Client::builder().default_headers(headers);
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+        if let Some(proxy) = self.proxy {
+            builder = builder.proxy(proxy);
+        }
+        if let Some(user_agent) = self.user_agent {
+            builder = builder.user_agent(user_agent);
+        }
+
+        Ok(Client {
+            inner: builder.build()?,
+            base_url: self.base_url.trim_end_matches('/').to_string(),
+            retry_policy: self.retry_policy,
+            #[cfg(feature = "stream")]
+            resume_policy: self.resume_policy,
+        })
+    }
+}
@@ -0,0 +1,698 @@
+use crate::types::{Summarizer, TokenUsage};
+use crate::{ChatInput, Client, Error, Message, Model, Response, Result, Role};
+use serde::{Deserialize, Serialize};
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The number of tokens priming the reply of the assistant.
+pub(crate) const TOKENS_PER_REPLY: usize = 3;
+
+/// Decides which of the oldest messages of a conversation are left out of a request.
+///
+/// The messages of a conversation are split into the pinned messages, which are always sent,
+/// and the history that follows them. Strategies only ever drop the oldest history messages.
+pub trait TrimStrategy: Send + Sync {
+    /// Computes how many of the oldest history messages to leave out of the request.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The parameters of the request, without messages.
+    /// * `pinned` - The messages that are always sent.
+    /// * `history` - The remaining messages, oldest first.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the number of messages to drop from the start of `history`, or an
+    /// `Error` if the messages cannot be measured.
+    fn trim(
+        &self,
+        input: &ChatInput,
+        pinned: &[Message<String>],
+        history: &[Message<String>],
+    ) -> Result<usize>;
+}
+
+/// A `TrimStrategy` that sends every message.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct KeepAll;
+
+impl TrimStrategy for KeepAll {
+    fn trim(&self, _: &ChatInput, _: &[Message<String>], _: &[Message<String>]) -> Result<usize> {
+        Ok(0)
+    }
+}
+
+/// A `TrimStrategy` that sends only the most recent history messages.
+#[derive(Debug, Copy, Clone)]
+pub struct SlidingWindow {
+    /// The maximum number of history messages to send.
+    pub max_messages: usize,
+}
+
+impl SlidingWindow {
+    /// Creates a `SlidingWindow` keeping at most `max_messages` history messages.
+    pub fn new(max_messages: usize) -> Self {
+        Self { max_messages }
+    }
+}
+
+impl TrimStrategy for SlidingWindow {
+    fn trim(
+        &self,
+        _: &ChatInput,
+        _: &[Message<String>],
+        history: &[Message<String>],
+    ) -> Result<usize> {
+        let dropped = history.len().saturating_sub(self.max_messages);
+        Ok(skip_tool_results(history, dropped))
+    }
+}
+
+/// Moves a cut point past the tool results at the start of the kept messages, so that tool
+/// results are never sent without the assistant message that requested them.
+///
+/// # Arguments
+///
+/// * `history` - The history messages, oldest first.
+/// * `dropped` - The number of oldest messages to drop.
+///
+/// # Returns
+///
+/// The number of oldest messages to drop, including any orphaned tool results.
+pub(crate) fn skip_tool_results(history: &[Message<String>], mut dropped: usize) -> usize {
+    while dropped > 0 && dropped < history.len() && history[dropped].role == Role::Tool {
+        dropped += 1;
+    }
+    dropped
+}
+
+/// Counts the prompt tokens of messages.
+#[derive(Clone, Default)]
+pub enum TokenCounter {
+    /// A rough estimate of one token per four bytes of text plus the chat format overhead.
+    #[default]
+    Estimate,
+    /// An exact count with a BPE tokenizer (when using the `tokenizer` feature).
+    #[cfg(feature = "tokenizer")]
+    Tokenizer(Arc<crate::tokenizer::Tokenizer>),
+}
+
+impl Debug for TokenCounter {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            TokenCounter::Estimate => f.write_str("Estimate"),
+            #[cfg(feature = "tokenizer")]
+            TokenCounter::Tokenizer(tokenizer) => {
+                write!(f, "Tokenizer({})", tokenizer.encoding().name())
+            }
+        }
+    }
+}
+
+impl TokenCounter {
+    /// Counts the prompt tokens of a list of messages, excluding the tokens priming the reply.
+    ///
+    /// # Arguments
+    ///
+    /// * `messages` - The messages to count.
+    /// * `model` - The model the messages are sent to.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the number of tokens, or an `Error::TokenizerError` if the
+    /// tokenizer cannot encode a message.
+    #[cfg_attr(not(feature = "tokenizer"), allow(unused_variables))]
+    pub fn count(&self, messages: &[Message<String>], model: &Model) -> Result<usize> {
+        match self {
+            TokenCounter::Estimate => Ok(messages.iter().map(estimate_tokens).sum()),
+            #[cfg(feature = "tokenizer")]
+            TokenCounter::Tokenizer(tokenizer) => Ok(tokenizer
+                .count_message_tokens(messages, model)?
+                .saturating_sub(TOKENS_PER_REPLY)),
+        }
+    }
+}
+
+/// Estimates the tokens of a message from the length of its text.
+fn estimate_tokens(message: &Message<String>) -> usize {
+    let text = message.content.len()
+        + message.name.as_ref().map_or(0, String::len)
+        + message
+            .tool_calls
+            .iter()
+            .flatten()
+            .map(|call| call.function.name.len() + call.function.arguments.len())
+            .sum::<usize>();
+    4 + text.div_ceil(4)
+}
+
+/// A `TrimStrategy` that drops the oldest history messages until the prompt fits the context
+/// window of the model, leaving room for the completion.
+///
+/// The latest user message and the messages answering it are never dropped. If they do not fit
+/// along with the pinned messages, trimming fails with `Error::ContextLengthExceeded`.
+#[derive(Debug, Clone, Default)]
+pub struct TokenBudget {
+    /// The maximum number of prompt and completion tokens. Defaults to the context window of
+    /// the model.
+    pub max_context_tokens: Option<usize>,
+    /// The number of tokens reserved for the completion. Defaults to the `max_tokens` of the
+    /// request, or the maximum output of the model capped at half of the context window.
+    pub reserved_tokens: Option<usize>,
+    /// The counter used to measure the messages.
+    pub counter: TokenCounter,
+}
+
+impl TokenBudget {
+    /// The context window assumed for models without registered metadata.
+    pub const FALLBACK_CONTEXT_TOKENS: usize = 4_096;
+
+    /// Creates a `TokenBudget` measuring messages with the given counter.
+    pub fn new(counter: TokenCounter) -> Self {
+        Self {
+            counter,
+            ..Default::default()
+        }
+    }
+
+    /// Sets the maximum number of prompt and completion tokens.
+    pub fn max_context_tokens(mut self, max_context_tokens: usize) -> Self {
+        self.max_context_tokens = Some(max_context_tokens);
+        self
+    }
+
+    /// Sets the number of tokens reserved for the completion.
+    pub fn reserved_tokens(mut self, reserved_tokens: usize) -> Self {
+        self.reserved_tokens = Some(reserved_tokens);
+        self
+    }
+
+    /// Computes the number of tokens available to the messages of a request.
+    pub fn prompt_budget(&self, input: &ChatInput) -> usize {
+        let context = self
+            .max_context_tokens
+            .or_else(|| input.model.context_window())
+            .unwrap_or(Self::FALLBACK_CONTEXT_TOKENS);
+        let reserved = match self.reserved_tokens.or(input.max_tokens) {
+            Some(reserved) => reserved,
+            // Models may output as much as their whole context window, which would leave no room
+            // for the prompt when the size of the completion is unknown.
+            None => input
+                .model
+                .max_output_tokens()
+                .unwrap_or(0)
+                .min(context / 2),
+        };
+        context.saturating_sub(reserved)
+    }
+}
+
+impl TrimStrategy for TokenBudget {
+    fn trim(
+        &self,
+        input: &ChatInput,
+        pinned: &[Message<String>],
+        history: &[Message<String>],
+    ) -> Result<usize> {
+        let budget = self.prompt_budget(input);
+        let question = history
+            .iter()
+            .rposition(|message| message.role == Role::User)
+            .unwrap_or(history.len());
+        let mut used = self.counter.count(pinned, &input.model)?
+            + self.counter.count(&history[question..], &input.model)?
+            + TOKENS_PER_REPLY;
+        if used > budget {
+            return Err(Error::ContextLengthExceeded {
+                required: used,
+                available: budget,
+            });
+        }
+        let mut kept = history.len() - question;
+        for message in history[..question].iter().rev() {
+            used += self
+                .counter
+                .count(std::slice::from_ref(message), &input.model)?;
+            if used > budget {
+                break;
+            }
+            kept += 1;
+        }
+        Ok(skip_tool_results(history, history.len() - kept))
+    }
+}
+
+/// Represents an ongoing conversation with the model.
+///
+/// A `Conversation` owns its messages and the parameters of its requests. Before each request,
+/// the configured `TrimStrategy` decides which of the oldest messages are left out, so that the
+/// request fits the context window. The leading messages of the conversation can be pinned so
+/// that they are always sent; by default, the leading system messages are pinned.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use chatgpt_client::types::{Conversation, TokenBudget};
+/// use chatgpt_client::{ChatInput, Client, Message};
+///
+/// # async fn run(client: Client) -> chatgpt_client::Result<()> {
+/// let mut conversation = Conversation::new(
+///     ChatInput::builder()
+///         .system("You are a helpful assistant.")
+///         .build(),
+/// )
+/// .strategy(TokenBudget::default());
+///
+/// conversation.push(Message::user("Hello!"));
+/// let response = conversation.send(&client).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Conversation {
+    input: ChatInput,
+    pinned: Option<usize>,
+    strategy: Arc<dyn TrimStrategy>,
+    summarizer: Option<Summarizer>,
+    summary: Option<String>,
+    summarized: usize,
+    usage: TokenUsage,
+    created_at: u64,
+}
+
+impl Debug for Conversation {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        f.debug_struct("Conversation")
+            .field("input", &self.input)
+            .field("pinned", &self.pinned)
+            .field("summarizer", &self.summarizer)
+            .field("summary", &self.summary)
+            .field("summarized", &self.summarized)
+            .field("usage", &self.usage)
+            .field("created_at", &self.created_at)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Conversation {
+    /// Creates a `Conversation` from the parameters of its requests.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The parameters of the requests. Its messages start the conversation.
+    ///
+    /// # Returns
+    ///
+    /// A new `Conversation` instance that sends every message.
+    pub fn new(input: ChatInput) -> Self {
+        Self {
+            input,
+            pinned: None,
+            strategy: Arc::new(KeepAll),
+            summarizer: None,
+            summary: None,
+            summarized: 0,
+            usage: TokenUsage::default(),
+            created_at: unix_time(),
+        }
+    }
+
+    /// Sets the strategy deciding which of the oldest messages are left out of requests.
+    pub fn strategy(mut self, strategy: impl TrimStrategy + 'static) -> Self {
+        self.strategy = Arc::new(strategy);
+        self
+    }
+
+    /// Sets the `Summarizer` compressing the oldest turns when the history exceeds its budget.
+    ///
+    /// The `TrimStrategy` still applies to the summary and the turns it does not cover.
+    pub fn summarizer(mut self, summarizer: Summarizer) -> Self {
+        self.summarizer = Some(summarizer);
+        self
+    }
+
+    /// Returns the cached summary of the oldest turns, if any.
+    pub fn summary(&self) -> Option<&str> {
+        self.summary.as_deref()
+    }
+
+    /// Returns the number of history messages covered by the summary.
+    pub fn summarized(&self) -> usize {
+        self.summarized
+    }
+
+    /// Returns the total token usage of the requests sent by this conversation, including the
+    /// requests summarizing its oldest turns.
+    pub fn usage(&self) -> TokenUsage {
+        self.usage
+    }
+
+    /// Returns the time the conversation was created, in seconds since the Unix epoch.
+    pub fn created_at(&self) -> u64 {
+        self.created_at
+    }
+
+    /// Creates a `ConversationRecord` holding the state of the conversation, for storage.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The identifier the conversation is stored under.
+    ///
+    /// # Returns
+    ///
+    /// A new `ConversationRecord` instance.
+    pub fn record(&self, id: impl Into<String>) -> ConversationRecord {
+        ConversationRecord {
+            metadata: ConversationMetadata {
+                id: id.into(),
+                model: self.input.model.clone(),
+                created_at: self.created_at,
+                updated_at: unix_time(),
+                usage: self.usage,
+            },
+            messages: self.input.messages.clone(),
+            summary: self.summary.clone(),
+            summarized: self.summarized,
+        }
+    }
+
+    /// Restores a conversation from a `ConversationRecord`.
+    ///
+    /// The trimming strategy, summarizer and other request parameters are not stored, and must
+    /// be set again on the restored conversation.
+    ///
+    /// # Arguments
+    ///
+    /// * `record` - The stored state of the conversation.
+    ///
+    /// # Returns
+    ///
+    /// A new `Conversation` instance that sends every message.
+    pub fn from_record(record: ConversationRecord) -> Self {
+        let input = ChatInput::builder()
+            .model(record.metadata.model)
+            .messages(record.messages)
+            .build();
+        Self {
+            summary: record.summary,
+            summarized: record.summarized,
+            usage: record.metadata.usage,
+            created_at: record.metadata.created_at,
+            ..Self::new(input)
+        }
+    }
+
+    /// Pins the first `n` messages, so that they are always sent.
+    pub fn pin_first(mut self, n: usize) -> Self {
+        self.pinned = Some(n);
+        self
+    }
+
+    /// Returns all of the messages of the conversation, including the ones left out of requests.
+    pub fn messages(&self) -> &[Message<String>] {
+        &self.input.messages
+    }
+
+    /// Returns the parameters of the requests, including all of the messages.
+    pub fn input(&self) -> &ChatInput {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the parameters of the requests.
+    pub fn input_mut(&mut self) -> &mut ChatInput {
+        &mut self.input
+    }
+
+    /// Appends a message to the conversation.
+    pub fn push(&mut self, message: impl Into<Message<String>>) {
+        self.input.messages.push(message.into());
+    }
+
+    /// Returns the number of leading messages that are always sent.
+    pub fn pinned(&self) -> usize {
+        let pinned = self.pinned.unwrap_or_else(|| {
+            self.input
+                .messages
+                .iter()
+                .take_while(|message| message.role == Role::System)
+                .count()
+        });
+        pinned.min(self.input.messages.len())
+    }
+
+    /// Splits the messages into the ones that are always sent, including the summary message, and
+    /// the history not covered by the summary.
+    fn split(&self) -> (Vec<Message<String>>, &[Message<String>]) {
+        let (pinned, history) = self.input.messages.split_at(self.pinned());
+        let mut sent = pinned.to_vec();
+        if let (Some(summarizer), Some(summary)) = (&self.summarizer, &self.summary) {
+            sent.push(summarizer.message(summary));
+        }
+        (sent, &history[self.summarized.min(history.len())..])
+    }
+
+    /// Returns the parameters of the requests without any messages.
+    fn template(&self) -> ChatInput {
+        ChatInput {
+            messages: Vec::new(),
+            ..self.input.clone()
+        }
+    }
+
+    /// Builds the next request, replacing summarized turns with the summary and leaving out the
+    /// messages dropped by the `TrimStrategy`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `ChatInput` to send, or an `Error` if the `TrimStrategy` fails.
+    pub fn request(&self) -> Result<ChatInput> {
+        let (mut sent, history) = self.split();
+        let template = self.template();
+        let dropped = self
+            .strategy
+            .trim(&template, &sent, history)?
+            .min(history.len());
+        let dropped = skip_tool_results(history, dropped);
+
+        sent.extend_from_slice(&history[dropped..]);
+        Ok(ChatInput {
+            messages: sent,
+            ..template
+        })
+    }
+
+    /// Folds the oldest turns into the summary if the history exceeds the budget of the
+    /// `Summarizer`. Does nothing if no summarizer is set.
+    ///
+    /// # Arguments
+    ///
+    /// * `client` - The client used to request the summary.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` that is an `Error` if the summary could not be generated.
+    pub async fn summarize(&mut self, client: &Client) -> Result<()> {
+        let Some(summarizer) = self.summarizer.clone() else {
+            return Ok(());
+        };
+        let (sent, history) = self.split();
+        let folded = summarizer.overflow(&self.template(), &sent, history)?;
+        if folded == 0 {
+            return Ok(());
+        }
+
+        let (summary, usage) = summarizer
+            .summarize(
+                client,
+                &self.input.model,
+                self.summary.as_deref(),
+                &history[..folded],
+            )
+            .await?;
+        let covered = self.input.messages.len() - self.pinned() - history.len();
+        self.usage += usage;
+        self.summary = Some(summary);
+        self.summarized = covered + folded;
+        Ok(())
+    }
+
+    /// Sends the next request and appends the reply of the assistant to the conversation.
+    ///
+    /// If a `Summarizer` is set, the oldest turns are summarized first when needed.
+    ///
+    /// # Arguments
+    ///
+    /// * `client` - The client used to send the request.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `Response` or an `Error`.
+    pub async fn send(&mut self, client: &Client) -> Result<Response> {
+        self.summarize(client).await?;
+        let response = client.completion(&self.request()?).await?;
+        self.usage += response.usage;
+        if let Some(choice) = response.choices.first() {
+            self.push(choice.message.clone());
+        }
+        Ok(response)
+    }
+}
+
+/// Returns the current time in seconds since the Unix epoch.
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+/// Represents the metadata of a stored conversation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConversationMetadata {
+    /// The identifier the conversation is stored under.
+    pub id: String,
+    /// The model of the conversation.
+    pub model: Model,
+    /// The time the conversation was created, in seconds since the Unix epoch.
+    pub created_at: u64,
+    /// The time the conversation was last stored, in seconds since the Unix epoch.
+    pub updated_at: u64,
+    /// The total token usage of the conversation.
+    pub usage: TokenUsage,
+}
+
+/// Represents the stored state of a `Conversation`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConversationRecord {
+    /// The metadata of the conversation.
+    pub metadata: ConversationMetadata,
+    /// All of the messages of the conversation.
+    pub messages: Vec<Message<String>>,
+    /// The cached summary of the oldest turns, if any.
+    pub summary: Option<String>,
+    /// The number of history messages covered by the summary.
+    pub summarized: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{FunctionCall, ToolCall};
+
+    /// A conversation whose history is a tool-call round trip followed by a question.
+    fn tool_round_trip() -> Conversation {
+        let tool_call = |id: &str| ToolCall {
+            id: id.to_string(),
+            r#type: "function".to_string(),
+            function: FunctionCall {
+                name: "weather".to_string(),
+                arguments: r#"{"city":"Paris"}"#.to_string(),
+            },
+        };
+        let mut conversation = Conversation::new(ChatInput::builder().system("Be brief.").build());
+        conversation.push(Message::user("Weather in Paris and Lyon?"));
+        conversation.push(Message {
+            tool_calls: Some(vec![tool_call("a"), tool_call("b")]),
+            ..Message::assistant(String::new())
+        });
+        conversation.push(Message::tool("a", "Sunny"));
+        conversation.push(Message::tool("b", "Rainy"));
+        conversation.push(Message::assistant("Sunny in Paris, rainy in Lyon."));
+        conversation.push(Message::user("Thanks!"));
+        conversation
+    }
+
+    /// Asserts that every tool result sent follows the assistant message requesting it.
+    fn assert_no_orphaned_tool_results(input: &ChatInput) {
+        for (position, message) in input.messages.iter().enumerate() {
+            if message.role == Role::Tool {
+                let requested = input.messages[..position]
+                    .iter()
+                    .rev()
+                    .find(|message| message.role != Role::Tool)
+                    .is_some_and(|message| message.tool_calls.is_some());
+                assert!(requested, "orphaned tool result in {:?}", input.messages);
+            }
+        }
+    }
+
+    #[test]
+    fn sliding_window_keeps_tool_results_with_their_call() {
+        for max_messages in 0..=7 {
+            let conversation = tool_round_trip().strategy(SlidingWindow::new(max_messages));
+            let request = conversation.request().unwrap();
+            assert_no_orphaned_tool_results(&request);
+            assert_eq!(request.messages[0].role, Role::System);
+        }
+        let request = tool_round_trip()
+            .strategy(SlidingWindow::new(3))
+            .request()
+            .unwrap();
+        assert_eq!(request.messages.len(), 3);
+        assert_eq!(
+            request.messages[1].content,
+            "Sunny in Paris, rainy in Lyon."
+        );
+    }
+
+    #[test]
+    fn token_budget_keeps_tool_results_with_their_call() {
+        for max_context_tokens in 0..200 {
+            let budget = TokenBudget::default()
+                .max_context_tokens(max_context_tokens)
+                .reserved_tokens(0);
+            match tool_round_trip().strategy(budget).request() {
+                Ok(request) => {
+                    assert_no_orphaned_tool_results(&request);
+                    assert_eq!(request.messages.last().unwrap().content, "Thanks!");
+                }
+                Err(err) => assert!(matches!(err, Error::ContextLengthExceeded { .. })),
+            }
+        }
+    }
+
+    #[test]
+    fn token_budget_never_drops_the_question() {
+        let mut conversation = tool_round_trip();
+        conversation.input_mut().messages[0].content = "Be brief. ".repeat(40);
+        let budget = TokenBudget::default()
+            .max_context_tokens(110)
+            .reserved_tokens(0);
+        let request = conversation.clone().strategy(budget.clone()).request();
+        assert!(matches!(
+            request,
+            Err(Error::ContextLengthExceeded { available: 110, .. })
+        ));
+
+        let request = conversation
+            .strategy(budget.max_context_tokens(120))
+            .request()
+            .unwrap();
+        assert_eq!(request.messages.len(), 2);
+        assert_eq!(request.messages[1].content, "Thanks!");
+    }
+
+    #[test]
+    fn prompt_budget_respects_explicit_reservations() {
+        let input = ChatInput::builder().model(Model::Gpt4o).build();
+        let budget = TokenBudget::default().max_context_tokens(1_000);
+        assert_eq!(
+            budget.clone().reserved_tokens(800).prompt_budget(&input),
+            200
+        );
+        assert_eq!(
+            budget.clone().reserved_tokens(10).prompt_budget(&input),
+            990
+        );
+
+        let input = ChatInput::builder()
+            .model(Model::Gpt4o)
+            .max_tokens(700)
+            .build();
+        assert_eq!(budget.prompt_budget(&input), 300);
+
+        // Without a known completion size, the maximum output is capped at half the window.
+        let input = ChatInput::builder().model(Model::Gpt4o).build();
+        assert_eq!(budget.prompt_budget(&input), 500);
+    }
+}
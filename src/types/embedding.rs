@@ -0,0 +1,243 @@
+use crate::types::{Model, TokenUsage};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// Represents the format in which the API returns embedding vectors.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EncodingFormat {
+    /// Vectors are returned as arrays of numbers.
+    #[default]
+    Float,
+    /// Vectors are returned as base64-encoded little-endian `f32`s, which is about four times
+    /// smaller to transfer.
+    Base64,
+}
+
+/// Represents the input for an embeddings API call.
+#[derive(Debug, Clone, Serialize)]
+pub struct EmbeddingInput {
+    /// The embedding model to use.
+    pub model: Model,
+    /// The texts to embed.
+    pub input: Vec<String>,
+    /// The optional number of dimensions of the vectors, for models that support shortening.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dimensions: Option<u32>,
+    /// The optional format of the returned vectors.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encoding_format: Option<EncodingFormat>,
+    /// The optional user identifier.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+}
+
+impl EmbeddingInput {
+    /// The maximum number of texts the API accepts in a single request.
+    pub const MAX_BATCH_SIZE: usize = 2048;
+
+    /// Creates an `EmbeddingInput` for the given texts.
+    ///
+    /// # Arguments
+    ///
+    /// * `model` - The embedding model to use, such as `Model::TextEmbedding3Small`.
+    /// * `input` - The texts to embed.
+    ///
+    /// # Returns
+    ///
+    /// A new `EmbeddingInput` instance.
+    pub fn new<I, S>(model: impl Into<Model>, input: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            model: model.into(),
+            input: input.into_iter().map(Into::into).collect(),
+            dimensions: None,
+            encoding_format: None,
+            user: None,
+        }
+    }
+
+    /// Sets the number of dimensions of the vectors.
+    pub fn dimensions(mut self, dimensions: u32) -> Self {
+        self.dimensions = Some(dimensions);
+        self
+    }
+
+    /// Sets the format of the returned vectors.
+    pub fn encoding_format(mut self, encoding_format: EncodingFormat) -> Self {
+        self.encoding_format = Some(encoding_format);
+        self
+    }
+
+    /// Sets the user identifier.
+    pub fn user(mut self, user: impl Into<String>) -> Self {
+        self.user = Some(user.into());
+        self
+    }
+}
+
+/// Represents the response from the embeddings API.
+#[derive(Debug, Clone, Deserialize)]
+pub struct EmbeddingResponse {
+    /// The object type, typically "list".
+    pub object: String,
+    /// The embeddings, ordered by the index of their input.
+    pub data: Vec<Embedding>,
+    /// The model used to compute the embeddings.
+    pub model: Model,
+    /// Information about the token usage of the request.
+    pub usage: TokenUsage,
+}
+
+impl EmbeddingResponse {
+    /// Returns the vectors, ordered by the index of their input.
+    pub fn vectors(&self) -> Vec<&[f32]> {
+        self.data
+            .iter()
+            .map(|embedding| embedding.embedding.as_slice())
+            .collect()
+    }
+}
+
+/// Represents the embedding of one input text.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Embedding {
+    /// The object type, typically "embedding".
+    pub object: String,
+    /// The index of the input text.
+    pub index: usize,
+    /// The embedding vector, decoded from base64 if that format was requested.
+    #[serde(deserialize_with = "float_or_base64")]
+    pub embedding: Vec<f32>,
+}
+
+impl Embedding {
+    /// Computes the cosine similarity with another embedding.
+    pub fn cosine_similarity(&self, other: &Embedding) -> f32 {
+        cosine_similarity(&self.embedding, &other.embedding)
+    }
+
+    /// Scales the vector to unit length.
+    pub fn normalize(&mut self) {
+        normalize(&mut self.embedding);
+    }
+}
+
+/// Deserializes a vector from an array of numbers or a base64 string of little-endian `f32`s.
+fn float_or_base64<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<f32>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Vector {
+        Float(Vec<f32>),
+        Base64(String),
+    }
+
+    match Vector::deserialize(deserializer)? {
+        Vector::Float(vector) => Ok(vector),
+        Vector::Base64(encoded) => {
+            let bytes = STANDARD.decode(encoded).map_err(serde::de::Error::custom)?;
+            if bytes.len() % 4 != 0 {
+                return Err(serde::de::Error::custom(
+                    "base64 embedding is not a sequence of f32",
+                ));
+            }
+            Ok(bytes
+                .chunks_exact(4)
+                .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+                .collect())
+        }
+    }
+}
+
+/// Computes the dot product of two vectors, over the length of the shorter one.
+pub fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+
+/// Computes the cosine similarity of two vectors.
+///
+/// # Returns
+///
+/// The similarity from -1.0 to 1.0, or 0.0 if either vector is zero. For unit vectors, such as
+/// the embeddings returned by OpenAI, this equals their `dot` product.
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let norms = dot(a, a).sqrt() * dot(b, b).sqrt();
+    if norms == 0.0 {
+        0.0
+    } else {
+        dot(a, b) / norms
+    }
+}
+
+/// Scales a vector to unit length in place. A zero vector is left unchanged.
+pub fn normalize(vector: &mut [f32]) {
+    let norm = dot(vector, vector).sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|value| *value /= norm);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn embedding(vector: serde_json::Value) -> serde_json::Result<Embedding> {
+        serde_json::from_value(json!({"object": "embedding", "index": 0, "embedding": vector}))
+    }
+
+    #[test]
+    fn float_vectors_are_deserialized() {
+        let embedding = embedding(json!([0.5, -1.0, 2.0])).unwrap();
+        assert_eq!(embedding.embedding, vec![0.5, -1.0, 2.0]);
+    }
+
+    #[test]
+    fn base64_vectors_round_trip() {
+        let vector = [0.5f32, -1.25, 3.0e-7, f32::MAX];
+        let bytes: Vec<u8> = vector
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect();
+        let decoded = embedding(json!(STANDARD.encode(bytes))).unwrap();
+        assert_eq!(decoded.embedding, vector);
+        assert_eq!(embedding(json!("")).unwrap().embedding, Vec::<f32>::new());
+    }
+
+    #[test]
+    fn base64_vectors_must_hold_whole_floats() {
+        for length in [1, 2, 3, 5, 7] {
+            let encoded = STANDARD.encode(vec![0u8; length]);
+            assert!(embedding(json!(encoded)).is_err(), "{length} bytes");
+        }
+        assert!(embedding(json!("not base64!")).is_err());
+    }
+
+    #[test]
+    fn cosine_similarity_of_zero_vectors_is_zero() {
+        assert_eq!(cosine_similarity(&[0.0, 0.0], &[1.0, 2.0]), 0.0);
+        assert_eq!(cosine_similarity(&[0.0, 0.0], &[0.0, 0.0]), 0.0);
+        assert_eq!(cosine_similarity(&[], &[]), 0.0);
+    }
+
+    #[test]
+    fn cosine_similarity_ignores_length() {
+        assert!((cosine_similarity(&[1.0, 2.0], &[2.0, 4.0]) - 1.0).abs() < 1e-6);
+        assert!((cosine_similarity(&[1.0, 2.0], &[-3.0, -6.0]) + 1.0).abs() < 1e-6);
+        assert!(cosine_similarity(&[1.0, 0.0], &[0.0, 5.0]).abs() < 1e-6);
+    }
+
+    #[test]
+    fn normalize_scales_to_unit_length() {
+        let mut vector = [3.0, 4.0];
+        normalize(&mut vector);
+        assert_eq!(vector, [0.6, 0.8]);
+
+        let mut zero = [0.0, 0.0];
+        normalize(&mut zero);
+        assert_eq!(zero, [0.0, 0.0]);
+    }
+}
@@ -0,0 +1,134 @@
+use crate::{Error, Result};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+/// Represents a map from token ids to a bias that is added to their logits before sampling.
+///
+/// Biases must be in the range `-100..=100`: values near `-100` effectively ban a token and
+/// values near `100` make it almost certain to be selected. Ranges are checked by `validate`,
+/// which the `Client` calls before sending a request.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+#[serde(transparent)]
+pub struct LogitBias(BTreeMap<u32, i16>);
+
+impl LogitBias {
+    /// The smallest allowed bias.
+    pub const MIN: i16 = -100;
+    /// The largest allowed bias.
+    pub const MAX: i16 = 100;
+
+    /// Creates an empty `LogitBias`.
+    ///
+    /// # Returns
+    ///
+    /// A new `LogitBias` instance without any entries.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the bias of a token, replacing any previous bias.
+    ///
+    /// # Arguments
+    ///
+    /// * `token` - The id of the token.
+    /// * `bias` - The bias to apply, in the range `-100..=100`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the updated `LogitBias` or `Error::InvalidLogitBias`.
+    pub fn with_token(mut self, token: u32, bias: i16) -> Result<Self> {
+        Self::check(token, bias)?;
+        self.0.insert(token, bias);
+        Ok(self)
+    }
+
+    /// Sets the same bias for all of the given tokens.
+    ///
+    /// # Arguments
+    ///
+    /// * `tokens` - The ids of the tokens.
+    /// * `bias` - The bias to apply, in the range `-100..=100`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the updated `LogitBias` or `Error::InvalidLogitBias`.
+    pub fn with_tokens(self, tokens: impl IntoIterator<Item = u32>, bias: i16) -> Result<Self> {
+        tokens
+            .into_iter()
+            .try_fold(self, |logit_bias, token| logit_bias.with_token(token, bias))
+    }
+
+    /// Sets the same bias for all of the tokens the given text is encoded into.
+    ///
+    /// # Arguments
+    ///
+    /// * `tokenizer` - The tokenizer of the model the request is sent to.
+    /// * `text` - The text whose tokens are biased, such as " Paris".
+    /// * `bias` - The bias to apply, in the range `-100..=100`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the updated `LogitBias`, `Error::InvalidLogitBias`, or
+    /// `Error::TokenizerError` if the text cannot be encoded.
+    #[cfg(feature = "tokenizer")]
+    pub fn with_text(
+        self,
+        tokenizer: &crate::tokenizer::Tokenizer,
+        text: &str,
+        bias: i16,
+    ) -> Result<Self> {
+        self.with_tokens(tokenizer.encode(text)?, bias)
+    }
+
+    /// Returns the bias of a token, if set.
+    pub fn get(&self, token: u32) -> Option<i16> {
+        self.0.get(&token).copied()
+    }
+
+    /// Returns `true` if no bias is set.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns an iterator over the token ids and their biases.
+    pub fn iter(&self) -> impl Iterator<Item = (u32, i16)> + '_ {
+        self.0.iter().map(|(&token, &bias)| (token, bias))
+    }
+
+    /// Checks that every bias is in the range `-100..=100`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` that is `Error::InvalidLogitBias` for the first out-of-range entry.
+    pub fn validate(&self) -> Result<()> {
+        self.iter()
+            .try_for_each(|(token, bias)| Self::check(token, bias))
+    }
+
+    fn check(token: u32, bias: i16) -> Result<()> {
+        if (Self::MIN..=Self::MAX).contains(&bias) {
+            Ok(())
+        } else {
+            Err(Error::InvalidLogitBias { token, bias })
+        }
+    }
+}
+
+impl FromIterator<(u32, i16)> for LogitBias {
+    /// Collects token ids and biases into a `LogitBias` without validating them.
+    fn from_iter<I: IntoIterator<Item = (u32, i16)>>(iter: I) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl From<BTreeMap<u32, i16>> for LogitBias {
+    fn from(map: BTreeMap<u32, i16>) -> Self {
+        Self(map)
+    }
+}
+
+impl From<HashMap<u32, i16>> for LogitBias {
+    fn from(map: HashMap<u32, i16>) -> Self {
+        map.into_iter().collect()
+    }
+}
@@ -1,4 +1,5 @@
-use serde::{Deserialize, Serialize};
+use crate::types::ToolCall;
+use serde::{Deserialize, Deserializer, Serialize};
 
 /// Represents the role of a message sender in the conversation.
 #[derive(Debug, Default, Copy, Clone, PartialEq, PartialOrd, Eq, Ord, Serialize, Deserialize)]
@@ -11,6 +12,20 @@ pub enum Role {
     User,
     /// The assistant role, representing the AI-generated responses.
     Assistant,
+    /// The tool role, carrying the result of a tool call back to the model.
+    Tool,
+}
+
+impl Role {
+    /// Returns the name of the role used by the API, such as "user".
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::System => "system",
+            Role::User => "user",
+            Role::Assistant => "assistant",
+            Role::Tool => "tool",
+        }
+    }
 }
 
 /// Represents a message with a specific role and content.
@@ -18,11 +33,31 @@ pub enum Role {
 pub struct Message<T> {
     /// The role of the message sender.
     pub role: Role,
-    /// The content of the message.
+    /// The content of the message. A `null` content, as sent with tool calls, is read as
+    /// the default value.
+    #[serde(
+        deserialize_with = "null_as_default",
+        bound(deserialize = "T: Deserialize<'de> + Default")
+    )]
     pub content: T,
     /// The optional name of the sender, only used if needed.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<T>,
+    /// The tool calls made by the assistant, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_calls: Option<Vec<ToolCall>>,
+    /// The identifier of the tool call a tool message responds to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<T>,
+}
+
+/// Deserializes a `null` value as the default value of `T`.
+fn null_as_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de> + Default,
+{
+    Ok(Option::<T>::deserialize(deserializer)?.unwrap_or_default())
 }
 
 impl<T> Message<T> {
@@ -38,8 +73,10 @@ impl<T> Message<T> {
     pub fn system(content: T) -> Self {
         Self {
             role: Role::System,
-            content: content,
+            content,
             name: None,
+            tool_calls: None,
+            tool_call_id: None,
         }
     }
 
@@ -57,6 +94,8 @@ impl<T> Message<T> {
             role: Role::User,
             content,
             name: None,
+            tool_calls: None,
+            tool_call_id: None,
         }
     }
 
@@ -74,6 +113,62 @@ impl<T> Message<T> {
             role: Role::Assistant,
             content,
             name: None,
+            tool_calls: None,
+            tool_call_id: None,
+        }
+    }
+
+    /// Creates a new `Message` with the tool role, carrying the result of a tool call.
+    ///
+    /// # Arguments
+    ///
+    /// * `tool_call_id` - The identifier of the tool call this message responds to.
+    /// * `content` - The result of the tool call.
+    ///
+    /// # Returns
+    ///
+    /// A new `Message` instance with the tool role.
+    pub fn tool(tool_call_id: T, content: T) -> Self {
+        Self {
+            role: Role::Tool,
+            content,
+            name: None,
+            tool_calls: None,
+            tool_call_id: Some(tool_call_id),
+        }
+    }
+}
+
+impl<T: Default> Message<T> {
+    /// Creates a new `Message` with the assistant role carrying the given tool calls.
+    ///
+    /// # Arguments
+    ///
+    /// * `tool_calls` - The tool calls made by the assistant.
+    ///
+    /// # Returns
+    ///
+    /// A new `Message` instance with the assistant role and empty content.
+    pub fn assistant_tool_calls(tool_calls: Vec<ToolCall>) -> Self {
+        Self {
+            role: Role::Assistant,
+            content: T::default(),
+            name: None,
+            tool_calls: Some(tool_calls),
+            tool_call_id: None,
+        }
+    }
+}
+
+impl From<Message<&str>> for Message<String> {
+    /// Converts a borrowed `Message` into an owned one.
+    fn from(message: Message<&str>) -> Self {
+        Self {
+            role: message.role,
+            content: message.content.to_owned(),
+            name: message.name.map(str::to_owned),
+            tool_calls: message.tool_calls,
+            tool_call_id: message.tool_call_id.map(str::to_owned),
         }
     }
 }
@@ -1,21 +1,55 @@
+use std::convert::Infallible;
 use std::fmt::{Display, Formatter, Result};
+use std::str::FromStr;
 
-use serde::Serialize;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 /// Represents the available language models for generating responses.
-#[derive(Debug, Default, Copy, Clone, PartialEq, Serialize)]
+///
+/// Models without a dedicated variant, such as fine-tuned models, dated snapshots or models
+/// served by a local server, are represented by `Model::Custom`.
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd, Eq, Ord, Hash)]
 #[allow(non_camel_case_types)]
 pub enum Model {
     /// The GPT-3.5-turbo model.
-    #[serde(rename = "gpt-3.5-turbo")]
     Gpt35Turbo,
     /// The default GPT-4 model.
     #[default]
-    #[serde(rename = "gpt-4")]
     Gpt4,
     /// The GPT-4 model with 32k tokens.
-    #[serde(rename = "gpt-4-32k")]
     Gpt4_32k,
+    /// The GPT-4 Turbo model.
+    Gpt4Turbo,
+    /// The GPT-4o model.
+    Gpt4o,
+    /// The GPT-4o mini model.
+    Gpt4oMini,
+    /// The small third-generation embedding model.
+    TextEmbedding3Small,
+    /// The large third-generation embedding model.
+    TextEmbedding3Large,
+    /// The second-generation Ada embedding model.
+    TextEmbeddingAda002,
+    /// Any other model, identified by its id.
+    Custom(String),
+}
+
+impl Model {
+    /// Returns the identifier of the model used by the API.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Model::Gpt35Turbo => "gpt-3.5-turbo",
+            Model::Gpt4 => "gpt-4",
+            Model::Gpt4_32k => "gpt-4-32k",
+            Model::Gpt4Turbo => "gpt-4-turbo",
+            Model::Gpt4o => "gpt-4o",
+            Model::Gpt4oMini => "gpt-4o-mini",
+            Model::TextEmbedding3Small => "text-embedding-3-small",
+            Model::TextEmbedding3Large => "text-embedding-3-large",
+            Model::TextEmbeddingAda002 => "text-embedding-ada-002",
+            Model::Custom(id) => id,
+        }
+    }
 }
 
 impl Display for Model {
@@ -29,11 +63,50 @@ impl Display for Model {
     ///
     /// A `Result` containing the formatted model name or an error.
     fn fmt(&self, f: &mut Formatter) -> Result {
-        let model_name = match self {
-            Model::Gpt35Turbo => "gpt-3.5-turbo",
-            Model::Gpt4 => "gpt-4",
-            Model::Gpt4_32k => "gpt-4-32k",
-        };
-        write!(f, "{model_name}")
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl From<String> for Model {
+    /// Converts a model identifier into a `Model`, falling back to `Model::Custom`.
+    fn from(id: String) -> Self {
+        match id.as_str() {
+            "gpt-3.5-turbo" => Model::Gpt35Turbo,
+            "gpt-4" => Model::Gpt4,
+            "gpt-4-32k" => Model::Gpt4_32k,
+            "gpt-4-turbo" => Model::Gpt4Turbo,
+            "gpt-4o" => Model::Gpt4o,
+            "gpt-4o-mini" => Model::Gpt4oMini,
+            "text-embedding-3-small" => Model::TextEmbedding3Small,
+            "text-embedding-3-large" => Model::TextEmbedding3Large,
+            "text-embedding-ada-002" => Model::TextEmbeddingAda002,
+            _ => Model::Custom(id),
+        }
+    }
+}
+
+impl From<&str> for Model {
+    fn from(id: &str) -> Self {
+        Model::from(id.to_string())
+    }
+}
+
+impl FromStr for Model {
+    type Err = Infallible;
+
+    fn from_str(id: &str) -> std::result::Result<Self, Self::Err> {
+        Ok(Model::from(id))
+    }
+}
+
+impl Serialize for Model {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Model {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        Ok(Model::from(String::deserialize(deserializer)?))
     }
 }
@@ -0,0 +1,230 @@
+use crate::types::{Model, TokenUsage};
+use crate::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{OnceLock, RwLock};
+
+/// Represents the limits and pricing of a model.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelInfo {
+    /// The maximum number of tokens in the prompt and completion combined.
+    pub context_window: usize,
+    /// The maximum number of tokens in the completion.
+    pub max_output_tokens: usize,
+    /// The price in USD per 1,000 prompt tokens.
+    pub input_price_per_1k: f64,
+    /// The price in USD per 1,000 completion tokens.
+    pub output_price_per_1k: f64,
+}
+
+impl ModelInfo {
+    /// Computes the price of the given token usage.
+    ///
+    /// # Arguments
+    ///
+    /// * `usage` - The token usage of a request.
+    ///
+    /// # Returns
+    ///
+    /// The price in USD.
+    pub fn cost(&self, usage: &TokenUsage) -> f64 {
+        (usage.prompt_tokens as f64 * self.input_price_per_1k
+            + usage.completion_tokens as f64 * self.output_price_per_1k)
+            / 1000.0
+    }
+}
+
+/// A process-wide registry of `ModelInfo` keyed by model id.
+///
+/// The registry comes with the metadata of the models known to `Model`. Entries can be added or
+/// overridden at runtime, either one by one with `register` or from a JSON file mapping model ids
+/// to `ModelInfo` with `load_file`.
+///
+/// Lookups fall back to the longest registered id that prefixes the requested one followed by a
+/// `-`, so dated snapshots such as `gpt-4o-2024-08-06` use the metadata of `gpt-4o`. Snapshots
+/// whose limits differ from their base model, such as `gpt-4-1106-preview`, are registered
+/// explicitly.
+#[derive(Debug)]
+pub struct ModelRegistry;
+
+impl ModelRegistry {
+    fn entries() -> &'static RwLock<HashMap<String, ModelInfo>> {
+        static ENTRIES: OnceLock<RwLock<HashMap<String, ModelInfo>>> = OnceLock::new();
+        ENTRIES.get_or_init(|| {
+            let info = |context_window, max_output_tokens, input_price, output_price| ModelInfo {
+                context_window,
+                max_output_tokens,
+                input_price_per_1k: input_price,
+                output_price_per_1k: output_price,
+            };
+            let gpt_4_turbo = info(128_000, 4_096, 0.01, 0.03);
+            let gpt_35_turbo_4k = info(4_096, 4_096, 0.0015, 0.002);
+            RwLock::new(HashMap::from([
+                (
+                    "gpt-3.5-turbo".to_string(),
+                    info(16_385, 4_096, 0.0005, 0.0015),
+                ),
+                ("gpt-3.5-turbo-0301".to_string(), gpt_35_turbo_4k),
+                ("gpt-3.5-turbo-0613".to_string(), gpt_35_turbo_4k),
+                (
+                    "gpt-3.5-turbo-16k".to_string(),
+                    info(16_385, 4_096, 0.003, 0.004),
+                ),
+                ("gpt-4".to_string(), info(8_192, 8_192, 0.03, 0.06)),
+                ("gpt-4-32k".to_string(), info(32_768, 8_192, 0.06, 0.12)),
+                ("gpt-4-turbo".to_string(), gpt_4_turbo),
+                ("gpt-4-turbo-preview".to_string(), gpt_4_turbo),
+                ("gpt-4-1106-preview".to_string(), gpt_4_turbo),
+                ("gpt-4-0125-preview".to_string(), gpt_4_turbo),
+                ("gpt-4-vision-preview".to_string(), gpt_4_turbo),
+                ("gpt-4-1106-vision-preview".to_string(), gpt_4_turbo),
+                ("gpt-4o".to_string(), info(128_000, 16_384, 0.0025, 0.01)),
+                (
+                    "gpt-4o-mini".to_string(),
+                    info(128_000, 16_384, 0.00015, 0.0006),
+                ),
+                (
+                    "text-embedding-3-small".to_string(),
+                    info(8_191, 0, 0.00002, 0.0),
+                ),
+                (
+                    "text-embedding-3-large".to_string(),
+                    info(8_191, 0, 0.00013, 0.0),
+                ),
+                (
+                    "text-embedding-ada-002".to_string(),
+                    info(8_191, 0, 0.0001, 0.0),
+                ),
+            ]))
+        })
+    }
+
+    /// Returns the metadata registered for a model id.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The identifier of the model.
+    ///
+    /// # Returns
+    ///
+    /// An `Option` containing the `ModelInfo` of the model or of its longest registered prefix.
+    pub fn get(id: &str) -> Option<ModelInfo> {
+        let entries = Self::entries()
+            .read()
+            .unwrap_or_else(|err| err.into_inner());
+        entries.get(id).copied().or_else(|| {
+            entries
+                .iter()
+                .filter(|(prefix, _)| {
+                    id.strip_prefix(prefix.as_str())
+                        .is_some_and(|suffix| suffix.starts_with('-'))
+                })
+                .max_by_key(|(prefix, _)| prefix.len())
+                .map(|(_, info)| *info)
+        })
+    }
+
+    /// Registers or overrides the metadata of a model.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The identifier of the model.
+    /// * `info` - The metadata of the model.
+    pub fn register(id: impl Into<String>, info: ModelInfo) {
+        Self::entries()
+            .write()
+            .unwrap_or_else(|err| err.into_inner())
+            .insert(id.into(), info);
+    }
+
+    /// Registers or overrides the metadata of the models listed in a JSON file.
+    ///
+    /// The file must contain an object mapping model ids to `ModelInfo` objects.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the JSON file.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` that is an `Error` if the file could not be read or parsed.
+    pub fn load_file(path: impl AsRef<Path>) -> Result<()> {
+        let content = std::fs::read_to_string(path)?;
+        let infos: HashMap<String, ModelInfo> = serde_json::from_str(&content)?;
+        Self::entries()
+            .write()
+            .unwrap_or_else(|err| err.into_inner())
+            .extend(infos);
+        Ok(())
+    }
+}
+
+impl Model {
+    /// Returns the metadata of the model from the `ModelRegistry`.
+    pub fn info(&self) -> Option<ModelInfo> {
+        ModelRegistry::get(self.as_str())
+    }
+
+    /// Returns the maximum number of tokens in the prompt and completion combined.
+    pub fn context_window(&self) -> Option<usize> {
+        self.info().map(|info| info.context_window)
+    }
+
+    /// Returns the maximum number of tokens in the completion.
+    pub fn max_output_tokens(&self) -> Option<usize> {
+        self.info().map(|info| info.max_output_tokens)
+    }
+}
+
+impl TokenUsage {
+    /// Computes the price of this usage with the pricing of the given model.
+    ///
+    /// # Arguments
+    ///
+    /// * `model` - The model the tokens were used with.
+    ///
+    /// # Returns
+    ///
+    /// An `Option` containing the price in USD, or `None` if the model is not registered.
+    pub fn cost(&self, model: &Model) -> Option<f64> {
+        model.info().map(|info| info.cost(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context_window(id: &str) -> Option<usize> {
+        ModelRegistry::get(id).map(|info| info.context_window)
+    }
+
+    #[test]
+    fn preview_snapshots_use_their_own_window() {
+        for id in [
+            "gpt-4-1106-preview",
+            "gpt-4-0125-preview",
+            "gpt-4-turbo-preview",
+            "gpt-4-vision-preview",
+            "gpt-4-turbo-2024-04-09",
+        ] {
+            assert_eq!(context_window(id), Some(128_000), "{id}");
+        }
+        assert_eq!(context_window("gpt-3.5-turbo-0613"), Some(4_096));
+        assert_eq!(context_window("gpt-3.5-turbo-16k-0613"), Some(16_385));
+    }
+
+    #[test]
+    fn dated_snapshots_use_their_base_model() {
+        assert_eq!(context_window("gpt-4-0613"), Some(8_192));
+        assert_eq!(context_window("gpt-4-32k-0613"), Some(32_768));
+        assert_eq!(context_window("gpt-4o-2024-08-06"), Some(128_000));
+        assert_eq!(
+            ModelRegistry::get("gpt-4o-mini-2024-07-18").map(|info| info.input_price_per_1k),
+            Some(0.00015)
+        );
+        assert_eq!(context_window("gpt-4o1"), None);
+        assert_eq!(context_window("unknown"), None);
+    }
+}
@@ -1,5 +1,6 @@
-use super::Message;
-use serde::Deserialize;
+use super::{Message, Model};
+use serde::{Deserialize, Serialize};
+use std::ops::AddAssign;
 
 /// Represents the response from the ChatGPT API.
 ///
@@ -13,6 +14,8 @@ pub struct Response {
     pub object: String,
     /// The timestamp of when the response was created.
     pub created: u64,
+    /// The model used to generate the response.
+    pub model: Model,
     /// The list of choices generated by the model.
     pub choices: Vec<Choice>,
     /// Information about the token usage in the response.
@@ -23,16 +26,28 @@ pub struct Response {
 ///
 /// This struct provides information about the number of tokens used in the prompt,
 /// the completion, and the total tokens used.
-#[derive(Debug, Deserialize)]
+#[derive(
+    Debug, Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
 pub struct TokenUsage {
     /// The number of tokens used in the prompt.
     pub prompt_tokens: u32,
-    /// The number of tokens used in the completion.
+    /// The number of tokens used in the completion, which is 0 for embeddings.
+    #[serde(default)]
     pub completion_tokens: u32,
     /// The total number of tokens used in the response.
     pub total_tokens: u32,
 }
 
+impl AddAssign for TokenUsage {
+    /// Adds the token counts of another usage, e.g. to total the usage of a conversation.
+    fn add_assign(&mut self, other: Self) {
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+        self.total_tokens += other.total_tokens;
+    }
+}
+
 /// Represents a choice in the ChatGPT API response.
 ///
 /// A choice is a generated message by the model, typically selected based on the highest
@@ -0,0 +1,104 @@
+use serde::Serialize;
+
+/// Represents the format the model must produce its output in.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ResponseFormat {
+    /// Plain text output.
+    Text,
+    /// Output that is a valid JSON object.
+    JsonObject,
+    /// Output that follows the given JSON schema.
+    JsonSchema {
+        /// The schema the output must follow.
+        json_schema: JsonSchema,
+    },
+}
+
+impl ResponseFormat {
+    /// Creates a `ResponseFormat` requiring output that strictly follows a JSON schema.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the schema.
+    /// * `schema` - The JSON schema the output must follow.
+    ///
+    /// # Returns
+    ///
+    /// A new `ResponseFormat::JsonSchema` instance.
+    pub fn json_schema(name: impl Into<String>, schema: serde_json::Value) -> Self {
+        ResponseFormat::JsonSchema {
+            json_schema: JsonSchema {
+                name: name.into(),
+                description: None,
+                schema,
+                strict: Some(true),
+            },
+        }
+    }
+}
+
+/// Represents a named JSON schema for structured output.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct JsonSchema {
+    /// The name of the schema.
+    pub name: String,
+    /// The optional description of the expected output.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// The JSON schema the output must follow.
+    pub schema: serde_json::Value,
+    /// The optional flag enforcing that the output follows the schema exactly.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub strict: Option<bool>,
+}
+
+/// Removes the Markdown code fences the model sometimes wraps JSON output in, along with their
+/// language tag, whether the fenced output spans several lines or a single one.
+pub(crate) fn strip_code_fences(content: &str) -> &str {
+    let content = content.trim();
+    let Some(fenced) = content.strip_prefix("```") else {
+        return content;
+    };
+    let fenced = fenced.trim_end();
+    let fenced = fenced.strip_suffix("```").unwrap_or(fenced);
+    // The language tag, such as `json`, is followed by a newline or a space.
+    let body = match fenced.split_once(char::is_whitespace) {
+        Some((tag, body))
+            if tag
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') =>
+        {
+            body
+        }
+        _ => fenced,
+    };
+    body.trim()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn output_without_fences_is_unchanged() {
+        assert_eq!(strip_code_fences(" {\"a\": 1}\n"), "{\"a\": 1}");
+    }
+
+    #[test]
+    fn tagged_fences_are_removed() {
+        assert_eq!(strip_code_fences("```json\n{\"a\": 1}\n```"), "{\"a\": 1}");
+    }
+
+    #[test]
+    fn untagged_fences_are_removed() {
+        assert_eq!(strip_code_fences("```\n{\"a\": 1}\n```\n"), "{\"a\": 1}");
+    }
+
+    #[test]
+    fn single_line_fences_are_removed() {
+        assert_eq!(strip_code_fences("```{\"a\":1}```"), "{\"a\":1}");
+        assert_eq!(strip_code_fences("```{\"a\": 1}```"), "{\"a\": 1}");
+        assert_eq!(strip_code_fences("```json {\"a\": 1}```"), "{\"a\": 1}");
+    }
+}
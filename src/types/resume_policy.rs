@@ -0,0 +1,87 @@
+use crate::{ChatInput, Error, Message};
+
+/// Configures how `Client::stream` resumes a response whose stream was interrupted (when using
+/// the `stream` feature).
+///
+/// When the connection drops before the finish reason, the request is sent again with the
+/// content received so far as an assistant message, followed by `prompt` asking the model to
+/// continue, or unchanged if no content was received. The continuation is stitched into the
+/// same stream. Responses with several choices or with tool calls are not resumed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResumePolicy {
+    /// The maximum number of times a response is resumed.
+    pub max_resumes: u32,
+    /// The instructions asking the model to continue its interrupted answer.
+    pub prompt: String,
+}
+
+impl Default for ResumePolicy {
+    /// Provides default values for `ResumePolicy`.
+    ///
+    /// # Returns
+    ///
+    /// A `ResumePolicy` resuming a response up to 3 times.
+    fn default() -> Self {
+        Self {
+            max_resumes: 3,
+            prompt: Self::DEFAULT_PROMPT.to_string(),
+        }
+    }
+}
+
+impl ResumePolicy {
+    /// The default instructions asking the model to continue its interrupted answer.
+    pub const DEFAULT_PROMPT: &'static str = "Your previous answer was interrupted. \
+        Continue it exactly where it stopped, without repeating any of it or adding any preamble.";
+
+    /// Creates a `ResumePolicy` that never resumes.
+    ///
+    /// # Returns
+    ///
+    /// A `ResumePolicy` with no resumes.
+    pub fn none() -> Self {
+        Self {
+            max_resumes: 0,
+            ..Default::default()
+        }
+    }
+
+    /// Sets the instructions asking the model to continue its interrupted answer.
+    pub fn prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.prompt = prompt.into();
+        self
+    }
+
+    /// Determines whether the given stream error interrupted the response.
+    ///
+    /// # Arguments
+    ///
+    /// * `error` - The error yielded by the stream.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the connection dropped or the stream ended before the finish reason.
+    pub fn is_resumable(&self, error: &Error) -> bool {
+        matches!(
+            error,
+            Error::EventStreamError(_) | Error::IncompleteStream { .. }
+        )
+    }
+
+    /// Builds the request continuing an interrupted response.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The original request.
+    /// * `partial` - The content received so far.
+    ///
+    /// # Returns
+    ///
+    /// The continuation request.
+    pub(crate) fn continuation(&self, input: &ChatInput, partial: &str) -> ChatInput {
+        let mut input = input.clone();
+        input.messages.push(Message::assistant(partial.to_string()));
+        input.messages.push(Message::user(self.prompt.clone()));
+        input
+    }
+}
@@ -0,0 +1,220 @@
+use crate::{ApiErrorKind, Error};
+use reqwest::{header::HeaderMap, StatusCode};
+use std::time::Duration;
+
+/// Configures how the `Client` retries requests that failed with a transient error.
+///
+/// Requests are retried on connection errors, timeouts, `408`, `409`, `429` and `5xx`
+/// responses, except errors that do not clear by waiting, such as an insufficient quota. The
+/// delay between attempts grows exponentially from `base_delay`, capped at `max_delay`, unless
+/// the server specifies one via the `retry-after`, `retry-after-ms` or `x-ratelimit-reset-*`
+/// headers. Delays requested by the server are capped at `max_delay` too.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    /// The maximum number of attempts, including the first one.
+    pub max_attempts: u32,
+    /// The delay before the first retry.
+    pub base_delay: Duration,
+    /// The upper bound of the delay, whether computed or requested by the server.
+    pub max_delay: Duration,
+    /// The fraction (0.0 to 1.0) of the computed delay that is randomized.
+    pub jitter: f64,
+}
+
+impl Default for RetryPolicy {
+    /// Provides default values for `RetryPolicy`.
+    ///
+    /// # Returns
+    ///
+    /// A `RetryPolicy` making up to 3 attempts, starting at 500ms with full jitter.
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            jitter: 1.0,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Creates a `RetryPolicy` that never retries.
+    ///
+    /// # Returns
+    ///
+    /// A `RetryPolicy` with a single attempt.
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Default::default()
+        }
+    }
+
+    /// Determines whether the given error is worth retrying.
+    ///
+    /// # Arguments
+    ///
+    /// * `error` - The error returned by the last attempt.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the error is transient.
+    pub fn is_retryable(&self, error: &Error) -> bool {
+        match error {
+            Error::RequestFailed(api_error) => {
+                // These errors persist however long the client waits, even when sent with a 429.
+                if matches!(
+                    api_error.kind,
+                    ApiErrorKind::InsufficientQuota | ApiErrorKind::ContextLengthExceeded
+                ) {
+                    return false;
+                }
+                let status = api_error.status;
+                matches!(
+                    status,
+                    StatusCode::REQUEST_TIMEOUT
+                        | StatusCode::CONFLICT
+                        | StatusCode::TOO_MANY_REQUESTS
+                ) || status.is_server_error()
+            }
+            Error::ReqwestClientError(err) => err.is_timeout() || err.is_connect(),
+            _ => false,
+        }
+    }
+
+    /// Computes the delay before the next attempt.
+    ///
+    /// # Arguments
+    ///
+    /// * `attempt` - The number of the attempt that just failed, starting at 1.
+    /// * `error` - The error returned by that attempt.
+    ///
+    /// # Returns
+    ///
+    /// The `Duration` to wait before retrying.
+    pub fn delay(&self, attempt: u32, error: &Error) -> Duration {
+        if let Error::RequestFailed(api_error) = error {
+            if let Some(delay) = delay_from_headers(&api_error.headers) {
+                return delay.min(self.max_delay);
+            }
+        }
+
+        let exponent = attempt.saturating_sub(1).min(31);
+        let delay = self
+            .base_delay
+            .saturating_mul(1 << exponent)
+            .min(self.max_delay);
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        delay.mul_f64(1.0 - jitter * fastrand::f64())
+    }
+}
+
+/// Reads the delay requested by the server from the rate-limit headers of a response.
+fn delay_from_headers(headers: &HeaderMap) -> Option<Duration> {
+    let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+
+    if let Some(millis) = header("retry-after-ms").and_then(|value| value.parse::<f64>().ok()) {
+        return Duration::try_from_secs_f64(millis.max(0.0) / 1000.0).ok();
+    }
+    if let Some(secs) = header("retry-after").and_then(|value| value.parse::<f64>().ok()) {
+        return Duration::try_from_secs_f64(secs.max(0.0)).ok();
+    }
+    ["x-ratelimit-reset-requests", "x-ratelimit-reset-tokens"]
+        .into_iter()
+        .filter_map(|name| header(name).and_then(parse_reset))
+        .max()
+}
+
+/// Parses a reset duration such as `1s`, `6m0s`, `20ms` or `1h2m3.5s`.
+fn parse_reset(value: &str) -> Option<Duration> {
+    let mut total = 0.0;
+    let mut rest = value.trim();
+    if rest.is_empty() {
+        return None;
+    }
+    while !rest.is_empty() {
+        let number_len = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(rest.len());
+        let number = rest[..number_len].parse::<f64>().ok()?;
+        rest = &rest[number_len..];
+        let unit_len = rest
+            .find(|c: char| c.is_ascii_digit() || c == '.')
+            .unwrap_or(rest.len());
+        let factor = match &rest[..unit_len] {
+            "h" => 3600.0,
+            "m" => 60.0,
+            "s" | "" => 1.0,
+            "ms" => 0.001,
+            _ => return None,
+        };
+        total += number * factor;
+        rest = &rest[unit_len..];
+    }
+    Duration::try_from_secs_f64(total).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ApiError;
+
+    fn rate_limited(name: &'static str, value: &str) -> Error {
+        let mut headers = HeaderMap::new();
+        headers.insert(name, value.parse().unwrap());
+        Error::RequestFailed(Box::new(ApiError::new(
+            StatusCode::TOO_MANY_REQUESTS,
+            headers,
+            String::new(),
+        )))
+    }
+
+    #[test]
+    fn header_delays_are_capped_at_max_delay() {
+        let policy = RetryPolicy::default();
+        for (name, value) in [
+            ("retry-after", "3600"),
+            ("retry-after-ms", "600000"),
+            ("x-ratelimit-reset-requests", "1h"),
+        ] {
+            assert_eq!(
+                policy.delay(1, &rate_limited(name, value)),
+                policy.max_delay
+            );
+        }
+        assert_eq!(
+            policy.delay(1, &rate_limited("retry-after", "2")),
+            Duration::from_secs(2)
+        );
+    }
+
+    #[test]
+    fn insufficient_quota_is_not_retried() {
+        let policy = RetryPolicy::default();
+        let body = r#"{"error":{"message":"quota","type":"insufficient_quota","code":"insufficient_quota"}}"#;
+        let quota = ApiError::new(StatusCode::TOO_MANY_REQUESTS, HeaderMap::new(), body.into());
+        assert!(!policy.is_retryable(&Error::RequestFailed(Box::new(quota))));
+        assert!(policy.is_retryable(&rate_limited("retry-after", "1")));
+    }
+
+    #[test]
+    fn timeouts_are_retried_whatever_their_type() {
+        let policy = RetryPolicy::default();
+        let body = r#"{"error":{"message":"timeout","type":"invalid_request_error","code":null}}"#;
+        for status in [StatusCode::REQUEST_TIMEOUT, StatusCode::CONFLICT] {
+            let error = ApiError::new(status, HeaderMap::new(), body.into());
+            assert!(policy.is_retryable(&Error::RequestFailed(Box::new(error))));
+        }
+    }
+
+    #[test]
+    fn invalid_header_delays_do_not_panic() {
+        let policy = RetryPolicy::default();
+        for value in ["inf", "-inf", "NaN", "1e30", "1e300"] {
+            assert!(policy.delay(1, &rate_limited("retry-after", value)) <= policy.max_delay);
+            assert!(policy.delay(1, &rate_limited("retry-after-ms", value)) <= policy.max_delay);
+        }
+        let reset = rate_limited("x-ratelimit-reset-tokens", "99999999999999999999h");
+        assert!(policy.delay(1, &reset) <= policy.max_delay);
+    }
+}
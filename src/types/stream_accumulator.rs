@@ -0,0 +1,153 @@
+use crate::types::{
+    Choice, Chunk, FunctionCall, Model, Response, Role, TokenUsage, ToolCall, ToolCallDelta,
+};
+use crate::{Error, Message, Result};
+use std::collections::BTreeMap;
+
+/// Reassembles the `Chunk`s of a streamed response into the `Response` a non-streaming call
+/// would have returned (when using the `stream` feature).
+///
+/// Each choice is tracked by its `ChunkChoice::index`, so responses with several choices are
+/// reassembled correctly regardless of how their chunks are interleaved.
+#[derive(Debug, Default, Clone)]
+pub struct StreamAccumulator {
+    id: Option<String>,
+    created: u64,
+    model: Model,
+    choices: BTreeMap<usize, ChoiceState>,
+    usage: TokenUsage,
+}
+
+/// The state of a choice being reassembled.
+#[derive(Debug, Default, Clone)]
+struct ChoiceState {
+    role: Option<Role>,
+    content: String,
+    tool_calls: BTreeMap<usize, ToolCall>,
+    finish_reason: Option<String>,
+}
+
+impl StreamAccumulator {
+    /// Creates an empty `StreamAccumulator`.
+    ///
+    /// # Returns
+    ///
+    /// A new `StreamAccumulator` instance.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a chunk to the response being reassembled.
+    ///
+    /// # Arguments
+    ///
+    /// * `chunk` - The next chunk of the stream.
+    pub fn push(&mut self, chunk: &Chunk) {
+        if self.id.is_none() {
+            self.id = Some(chunk.id.clone());
+            self.created = chunk.created;
+            self.model = chunk.model.clone();
+        }
+
+        if let Some(usage) = chunk.usage {
+            self.usage = usage;
+        }
+
+        for choice in &chunk.choices {
+            let state = self.choices.entry(choice.index).or_default();
+            if let Some(role) = choice.delta.role {
+                state.role = Some(role);
+            }
+            if let Some(content) = &choice.delta.content {
+                state.content.push_str(content);
+            }
+            for delta in choice.delta.tool_calls.iter().flatten() {
+                merge_tool_call(&mut state.tool_calls, delta);
+            }
+            if let Some(finish_reason) = &choice.finish_reason {
+                state.finish_reason = Some(finish_reason.clone());
+            }
+        }
+    }
+
+    /// Returns the content accumulated so far for a choice.
+    pub fn content(&self, index: usize) -> Option<&str> {
+        self.choices.get(&index).map(|state| state.content.as_str())
+    }
+
+    /// Returns `true` if every choice received so far has a finish reason.
+    pub fn is_finished(&self) -> bool {
+        !self.choices.is_empty()
+            && self
+                .choices
+                .values()
+                .all(|state| state.finish_reason.is_some())
+    }
+
+    /// Builds the `Response` from the chunks received so far.
+    ///
+    /// Choices without a finish reason have an empty `finish_reason`. The usage is empty unless it
+    /// was requested with `StreamOptions::include_usage`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the assembled `Response`, or an `Error` if no chunk was received.
+    pub fn finish(self) -> Result<Response> {
+        let id = self
+            .id
+            .ok_or_else(|| Error::ResponseError("There is no chunk in response".to_string()))?;
+
+        let choices = self
+            .choices
+            .into_iter()
+            .map(|(index, state)| {
+                let tool_calls = (!state.tool_calls.is_empty())
+                    .then(|| state.tool_calls.into_values().collect());
+                Choice {
+                    index: index as u32,
+                    message: Message {
+                        role: state.role.unwrap_or(Role::Assistant),
+                        tool_calls,
+                        ..Message::assistant(state.content)
+                    },
+                    finish_reason: state.finish_reason.unwrap_or_default(),
+                }
+            })
+            .collect();
+
+        Ok(Response {
+            id,
+            object: "chat.completion".to_string(),
+            created: self.created,
+            model: self.model,
+            choices,
+            usage: self.usage,
+        })
+    }
+}
+
+/// Merges a streamed fragment into the tool call with the same index.
+fn merge_tool_call(tool_calls: &mut BTreeMap<usize, ToolCall>, delta: &ToolCallDelta) {
+    let tool_call = tool_calls.entry(delta.index).or_insert_with(|| ToolCall {
+        id: String::new(),
+        r#type: "function".to_string(),
+        function: FunctionCall {
+            name: String::new(),
+            arguments: String::new(),
+        },
+    });
+    if let Some(id) = &delta.id {
+        tool_call.id.clone_from(id);
+    }
+    if let Some(r#type) = &delta.r#type {
+        tool_call.r#type.clone_from(r#type);
+    }
+    if let Some(function) = &delta.function {
+        if let Some(name) = &function.name {
+            tool_call.function.name.push_str(name);
+        }
+        if let Some(arguments) = &function.arguments {
+            tool_call.function.arguments.push_str(arguments);
+        }
+    }
+}
@@ -1,52 +1,124 @@
 /// Represents an item in the stream of responses from the ChatGPT API when using the `stream` feature.
 ///
 /// This enum is used when processing streaming responses from the ChatGPT API. It handles the start, content,
-/// and finish reason for each chunk in the stream.
-#[derive(Debug)]
+/// and finish reason for each chunk in the stream. Every item carries the `index` of the choice it belongs
+/// to, so the items of responses with several choices (`n > 1`) can be told apart.
+#[derive(Debug, Clone)]
 pub enum StreamItem {
     /// Represents the start of a new response chunk.
     ///
     /// This variant contains metadata about the response chunk, such as the unique identifier, object type,
     /// creation timestamp, model, and role.
     Start {
+        index: usize,
         id: String,
         object: String,
         created: u64,
-        model: String,
+        model: crate::types::Model,
         role: crate::types::Role,
     },
     /// Represents the content of the response chunk.
     ///
     /// This variant contains the generated text content from the model.
-    Content(String),
+    Content { index: usize, content: String },
+    /// Represents fragments of tool calls.
+    ///
+    /// This variant contains pieces of the tool calls made by the model, to be merged by their `index`.
+    ToolCalls {
+        index: usize,
+        tool_calls: Vec<crate::types::ToolCallDelta>,
+    },
     /// Represents the finish reason for the response chunk.
     ///
     /// This variant contains the reason for finishing the generation, such as "stop" (reached stop sequence),
     /// "length" (reached max tokens), or "eos" (end of sentence).
-    FinishReason(String),
+    FinishReason { index: usize, reason: String },
+    /// Represents a chunk, or a choice of a chunk, that carries nothing to process.
+    ///
+    /// Servers send such chunks as keep-alives or with empty deltas. The `index` is `None` when
+    /// the chunk has no choices.
+    Empty { index: Option<usize> },
+    /// Represents the token usage of the whole request.
+    ///
+    /// This variant is only sent, after the finish reasons, when requested with
+    /// `StreamOptions::include_usage`.
+    Usage(crate::types::TokenUsage),
+    /// Represents the end of the stream, signaled by the `[DONE]` sentinel.
+    ///
+    /// This is always the last item of a stream that finished cleanly.
+    Done,
 }
 
-/// Implements the conversion from a `Chunk` to a `StreamItem`.
-///
-/// This implementation is used to convert the data received from the ChatGPT API into a more manageable `StreamItem`
-/// format that can be processed by the client.
-impl From<crate::types::Chunk> for StreamItem {
-    fn from(mut chunk: crate::types::Chunk) -> Self {
-        let choice = chunk.choices.first_mut().unwrap();
-        if let Some(role) = choice.delta.role.take() {
-            StreamItem::Start {
-                id: chunk.id,
-                object: chunk.object,
-                created: chunk.created,
-                model: chunk.model,
-                role,
+impl StreamItem {
+    /// Returns the index of the choice the item belongs to, or `None` if it belongs to none.
+    pub fn index(&self) -> Option<usize> {
+        match self {
+            StreamItem::Start { index, .. }
+            | StreamItem::Content { index, .. }
+            | StreamItem::ToolCalls { index, .. }
+            | StreamItem::FinishReason { index, .. } => Some(*index),
+            StreamItem::Empty { index } => *index,
+            StreamItem::Usage(_) | StreamItem::Done => None,
+        }
+    }
+
+    /// Converts a `Chunk` into the `StreamItem`s of all its choices.
+    ///
+    /// The items of a choice are returned in the order `Start`, `Content`, `ToolCalls` and
+    /// `FinishReason`, skipping the parts missing from its delta. A choice with nothing to process
+    /// yields `StreamItem::Empty`, as does a chunk with neither choices nor usage. The usage of the
+    /// chunk, if any, is returned last as `StreamItem::Usage`.
+    ///
+    /// # Arguments
+    ///
+    /// * `chunk` - The chunk received from the API.
+    ///
+    /// # Returns
+    ///
+    /// The `StreamItem`s of the chunk, ordered by choice.
+    pub fn from_chunk(chunk: crate::types::Chunk) -> Vec<StreamItem> {
+        let crate::types::Chunk {
+            id,
+            object,
+            created,
+            model,
+            choices,
+            usage,
+        } = chunk;
+
+        let mut items = Vec::new();
+        for choice in choices {
+            let index = choice.index;
+            let count = items.len();
+            if let Some(role) = choice.delta.role {
+                items.push(StreamItem::Start {
+                    index,
+                    id: id.clone(),
+                    object: object.clone(),
+                    created,
+                    model: model.clone(),
+                    role,
+                });
+            }
+            if let Some(content) = choice.delta.content {
+                items.push(StreamItem::Content { index, content });
             }
-        } else if let Some(content) = choice.delta.content.take() {
-            StreamItem::Content(content)
-        } else if let Some(reason) = choice.finish_reason.take() {
-            StreamItem::FinishReason(reason)
-        } else {
-            panic!("Unexpected response chunk: {:?}", chunk);
+            if let Some(tool_calls) = choice.delta.tool_calls {
+                items.push(StreamItem::ToolCalls { index, tool_calls });
+            }
+            if let Some(reason) = choice.finish_reason {
+                items.push(StreamItem::FinishReason { index, reason });
+            }
+            if items.len() == count {
+                items.push(StreamItem::Empty { index: Some(index) });
+            }
+        }
+        if let Some(usage) = usage {
+            items.push(StreamItem::Usage(usage));
+        }
+        if items.is_empty() {
+            items.push(StreamItem::Empty { index: None });
         }
+        items
     }
 }
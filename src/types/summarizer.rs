@@ -0,0 +1,229 @@
+use crate::types::conversation::skip_tool_results;
+use crate::types::{TokenBudget, TokenUsage};
+use crate::{ChatInput, Client, Error, Message, Model, Result, Role};
+
+/// Compresses the oldest turns of a `Conversation` into a summary when its history exceeds the
+/// token budget.
+///
+/// The summary is cached by the conversation and sent in place of the turns it covers. When the
+/// history grows over budget again, the previous summary and the next oldest turns are folded
+/// into a new summary, so every turn is only summarized once.
+#[derive(Debug, Clone)]
+pub struct Summarizer {
+    /// The instructions given to the model to write the summary.
+    pub prompt: String,
+    /// The model writing the summary. Defaults to the model of the conversation.
+    pub model: Option<Model>,
+    /// The budget that triggers summarization when exceeded.
+    pub budget: TokenBudget,
+    /// The number of most recent history messages that are never summarized.
+    pub keep_recent: usize,
+    /// The maximum length of the summary in tokens, reserved in the budget when turns are folded.
+    pub summary_tokens: usize,
+    /// The role of the message carrying the summary.
+    pub role: Role,
+}
+
+impl Default for Summarizer {
+    /// Provides default values for `Summarizer`.
+    ///
+    /// # Returns
+    ///
+    /// A `Summarizer` with a generic prompt that keeps the 4 most recent messages and sends a
+    /// summary of up to 512 tokens as a system message.
+    fn default() -> Self {
+        Self {
+            prompt: Self::DEFAULT_PROMPT.to_string(),
+            model: None,
+            budget: TokenBudget::default(),
+            keep_recent: 4,
+            summary_tokens: 512,
+            role: Role::System,
+        }
+    }
+}
+
+impl Summarizer {
+    /// The default instructions given to the model to write the summary.
+    pub const DEFAULT_PROMPT: &'static str = "Summarize the conversation below concisely. \
+        Preserve the facts, names, decisions and open questions needed to continue it. \
+        If a previous summary is given, update it with the new turns.";
+
+    /// Sets the instructions given to the model to write the summary.
+    pub fn prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.prompt = prompt.into();
+        self
+    }
+
+    /// Sets the model writing the summary.
+    pub fn model(mut self, model: impl Into<Model>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    /// Sets the budget that triggers summarization when exceeded.
+    pub fn budget(mut self, budget: TokenBudget) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    /// Sets the number of most recent history messages that are never summarized.
+    pub fn keep_recent(mut self, keep_recent: usize) -> Self {
+        self.keep_recent = keep_recent;
+        self
+    }
+
+    /// Sets the maximum length of the summary in tokens.
+    pub fn summary_tokens(mut self, summary_tokens: usize) -> Self {
+        self.summary_tokens = summary_tokens;
+        self
+    }
+
+    /// Sets the role of the message carrying the summary.
+    pub fn role(mut self, role: Role) -> Self {
+        self.role = role;
+        self
+    }
+
+    /// Builds the message sending a summary to the model.
+    pub(crate) fn message(&self, summary: &str) -> Message<String> {
+        Message {
+            role: self.role,
+            ..Message::system(format!("Summary of the earlier conversation:\n{summary}"))
+        }
+    }
+
+    /// Computes how many of the oldest unsummarized messages must be folded into the summary.
+    ///
+    /// Once the request exceeds the budget, turns are folded until it fits with room for a new
+    /// summary of up to `summary_tokens`, on top of the current summary it replaces.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The parameters of the request, without messages.
+    /// * `sent` - The pinned messages and the current summary message, which are always sent.
+    /// * `unsummarized` - The history messages not covered by the summary, oldest first.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the number of messages to summarize, which is 0 if the request fits
+    /// the budget, or an `Error` if the messages cannot be measured.
+    pub(crate) fn overflow(
+        &self,
+        input: &ChatInput,
+        sent: &[Message<String>],
+        unsummarized: &[Message<String>],
+    ) -> Result<usize> {
+        let counter = &self.budget.counter;
+        let budget = self.budget.prompt_budget(input);
+        let mut used =
+            counter.count(sent, &input.model)? + counter.count(unsummarized, &input.model)?;
+        if used <= budget {
+            return Ok(0);
+        }
+        let foldable = unsummarized.len().saturating_sub(self.keep_recent);
+        let reserved = counter.count(&[self.message("")], &input.model)? + self.summary_tokens;
+
+        let mut folded = 0;
+        while used + reserved > budget && folded < foldable {
+            used -= counter.count(&unsummarized[folded..=folded], &input.model)?;
+            folded += 1;
+        }
+        Ok(skip_tool_results(unsummarized, folded))
+    }
+
+    /// Asks the model to fold turns into the previous summary.
+    ///
+    /// # Arguments
+    ///
+    /// * `client` - The client used to send the request.
+    /// * `model` - The model of the conversation, used if no summarizer model is set.
+    /// * `previous` - The previous summary, if any.
+    /// * `turns` - The messages to fold into the summary.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the new summary and the token usage of the request, or an `Error`.
+    pub async fn summarize(
+        &self,
+        client: &Client,
+        model: &Model,
+        previous: Option<&str>,
+        turns: &[Message<String>],
+    ) -> Result<(String, TokenUsage)> {
+        let mut transcript = String::new();
+        if let Some(previous) = previous {
+            transcript.push_str(&format!("Previous summary:\n{previous}\n\n"));
+        }
+        transcript.push_str("New turns:\n");
+        for turn in turns {
+            let content = match &turn.tool_calls {
+                Some(tool_calls) if turn.content.is_empty() => tool_calls
+                    .iter()
+                    .map(|call| format!("{}({})", call.function.name, call.function.arguments))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                _ => turn.content.clone(),
+            };
+            transcript.push_str(&format!("{}: {}\n", turn.role.as_str(), content));
+        }
+
+        let input = ChatInput::builder()
+            .model(self.model.clone().unwrap_or_else(|| model.clone()))
+            .system(self.prompt.clone())
+            .user(transcript)
+            .max_tokens(self.summary_tokens)
+            .build();
+        let response = client.completion(&input).await?;
+        let usage = response.usage;
+        response
+            .choices
+            .into_iter()
+            .next()
+            .map(|choice| (choice.message.content, usage))
+            .ok_or_else(|| Error::ResponseError("There is no choice in response".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn folding_leaves_room_for_the_new_summary() {
+        let summarizer = Summarizer::default()
+            .keep_recent(0)
+            .summary_tokens(30)
+            .budget(
+                TokenBudget::default()
+                    .max_context_tokens(150)
+                    .reserved_tokens(0),
+            );
+        let input = ChatInput::builder().model(Model::Gpt4).build();
+        let counter = &summarizer.budget.counter;
+        let pinned = vec![Message::system("Be brief.".to_string())];
+        let mut sent = pinned.clone();
+        sent.push(summarizer.message(&"x".repeat(20)));
+        let unsummarized = vec![Message::user("y".repeat(36)); 10];
+
+        let folded = summarizer.overflow(&input, &sent, &unsummarized).unwrap();
+        // The new summary replaces the shorter current one and may be as long as allowed.
+        let mut request = pinned;
+        request.push(summarizer.message(&"z".repeat(4 * summarizer.summary_tokens)));
+        request.extend_from_slice(&unsummarized[folded..]);
+        let used = counter.count(&request, &input.model).unwrap();
+        assert!(used <= 150, "{used} tokens after folding {folded} turns");
+    }
+
+    #[test]
+    fn nothing_is_folded_within_budget() {
+        let summarizer = Summarizer::default().keep_recent(0).budget(
+            TokenBudget::default()
+                .max_context_tokens(1_000)
+                .reserved_tokens(0),
+        );
+        let input = ChatInput::builder().model(Model::Gpt4).build();
+        let unsummarized = vec![Message::user("Hello!".to_string()); 4];
+        assert_eq!(summarizer.overflow(&input, &[], &unsummarized).unwrap(), 0);
+    }
+}
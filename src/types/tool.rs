@@ -0,0 +1,109 @@
+use serde::{ser::SerializeMap, Deserialize, Serialize, Serializer};
+
+/// Represents a tool the model may call, described by a JSON schema of its parameters.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Tool {
+    /// The type of the tool. Currently, only "function" is supported.
+    pub r#type: String,
+    /// The definition of the function.
+    pub function: FunctionDefinition,
+}
+
+impl Tool {
+    /// Creates a new function `Tool`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the function.
+    /// * `description` - A description of what the function does.
+    /// * `parameters` - The JSON schema of the function's parameters.
+    ///
+    /// # Returns
+    ///
+    /// A new `Tool` instance with the "function" type.
+    pub fn function(
+        name: impl Into<String>,
+        description: impl Into<String>,
+        parameters: serde_json::Value,
+    ) -> Self {
+        Self {
+            r#type: "function".to_string(),
+            function: FunctionDefinition {
+                name: name.into(),
+                description: Some(description.into()),
+                parameters: Some(parameters),
+                strict: None,
+            },
+        }
+    }
+}
+
+/// Represents the definition of a function the model may call.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FunctionDefinition {
+    /// The name of the function.
+    pub name: String,
+    /// The optional description of what the function does.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// The optional JSON schema of the function's parameters.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parameters: Option<serde_json::Value>,
+    /// The optional flag enforcing that the arguments follow the schema exactly.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub strict: Option<bool>,
+}
+
+/// Controls which tool, if any, the model calls.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ToolChoice {
+    /// The model does not call any tool.
+    None,
+    /// The model decides whether to call tools.
+    Auto,
+    /// The model must call one or more tools.
+    Required,
+    /// The model must call the function with the given name.
+    Function(String),
+}
+
+impl Serialize for ToolChoice {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            ToolChoice::None => serializer.serialize_str("none"),
+            ToolChoice::Auto => serializer.serialize_str("auto"),
+            ToolChoice::Required => serializer.serialize_str("required"),
+            ToolChoice::Function(name) => {
+                #[derive(Serialize)]
+                struct Name<'a> {
+                    name: &'a str,
+                }
+
+                let mut map = serializer.serialize_map(Some(2))?;
+                map.serialize_entry("type", "function")?;
+                map.serialize_entry("function", &Name { name })?;
+                map.end()
+            }
+        }
+    }
+}
+
+/// Represents a call of a tool made by the model.
+#[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Serialize, Deserialize)]
+pub struct ToolCall {
+    /// The unique identifier of the call, referenced by the tool message carrying its result.
+    pub id: String,
+    /// The type of the tool. Currently, only "function" is supported.
+    pub r#type: String,
+    /// The function called by the model.
+    pub function: FunctionCall,
+}
+
+/// Represents the name and arguments of a function called by the model.
+#[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Serialize, Deserialize)]
+pub struct FunctionCall {
+    /// The name of the function.
+    pub name: String,
+    /// The arguments of the call as a JSON-encoded string.
+    pub arguments: String,
+}
@@ -0,0 +1,251 @@
+use crate::types::{Tool, ToolCall};
+use crate::{Error, Message, Response, Result};
+use serde::{de::DeserializeOwned, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::{Debug, Formatter};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::task::JoinSet;
+
+type ToolFuture = Pin<Box<dyn Future<Output = Result<String>> + Send>>;
+type ToolHandler = Arc<dyn Fn(&str) -> ToolFuture + Send + Sync>;
+
+/// A registry of Rust functions that the model can call as tools.
+///
+/// Each function is registered with a name, a description and the JSON schema of its arguments,
+/// which are deserialized into the function's argument type with serde. The registry is used by
+/// `Client::run_with_tools` to execute the tool calls made by the model.
+#[derive(Clone)]
+pub struct ToolRegistry {
+    tools: BTreeMap<String, (Tool, ToolHandler)>,
+    pub(crate) max_iterations: usize,
+}
+
+impl Default for ToolRegistry {
+    fn default() -> Self {
+        Self {
+            tools: BTreeMap::new(),
+            max_iterations: Self::DEFAULT_MAX_ITERATIONS,
+        }
+    }
+}
+
+impl Debug for ToolRegistry {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        f.debug_struct("ToolRegistry")
+            .field("tools", &self.tools.keys().collect::<Vec<_>>())
+            .field("max_iterations", &self.max_iterations)
+            .finish()
+    }
+}
+
+impl ToolRegistry {
+    /// The default maximum number of requests made by `Client::run_with_tools`.
+    pub const DEFAULT_MAX_ITERATIONS: usize = 10;
+
+    /// Creates an empty `ToolRegistry`.
+    ///
+    /// # Returns
+    ///
+    /// A new `ToolRegistry` instance without any tools.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum number of requests made before giving up on a final answer.
+    pub fn max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    /// Registers an async function as a tool.
+    ///
+    /// The function's result is serialized to JSON and sent back to the model; string results
+    /// are sent as-is.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name the model uses to call the function.
+    /// * `description` - A description of what the function does.
+    /// * `parameters` - The JSON schema of the function's arguments.
+    /// * `function` - The function to call with the deserialized arguments.
+    ///
+    /// # Returns
+    ///
+    /// The updated `ToolRegistry`.
+    pub fn register<A, R, F, Fut>(
+        mut self,
+        name: impl Into<String>,
+        description: impl Into<String>,
+        parameters: serde_json::Value,
+        function: F,
+    ) -> Self
+    where
+        A: DeserializeOwned,
+        R: Serialize,
+        F: Fn(A) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<R>> + Send + 'static,
+    {
+        let name = name.into();
+        let tool = Tool::function(name.clone(), description, parameters);
+        let function = Arc::new(function);
+        let handler: ToolHandler = Arc::new(move |arguments: &str| {
+            let arguments = if arguments.trim().is_empty() {
+                "{}"
+            } else {
+                arguments
+            };
+            match serde_json::from_str::<A>(arguments) {
+                Ok(arguments) => {
+                    let future = function(arguments);
+                    Box::pin(async move {
+                        match serde_json::to_value(future.await?)? {
+                            serde_json::Value::String(output) => Ok(output),
+                            output => Ok(output.to_string()),
+                        }
+                    })
+                }
+                Err(err) => Box::pin(async move { Err(err.into()) }),
+            }
+        });
+        self.tools.insert(name, (tool, handler));
+        self
+    }
+
+    /// Returns the definitions of the registered tools, to be sent in `ChatInput::tools`.
+    pub fn definitions(&self) -> Vec<Tool> {
+        self.tools.values().map(|(tool, _)| tool.clone()).collect()
+    }
+
+    /// Executes the given tool calls concurrently.
+    ///
+    /// Unknown tools, invalid arguments and errors returned by the functions are reported to the
+    /// model in the content of the tool message, so that it can correct itself.
+    ///
+    /// # Arguments
+    ///
+    /// * `tool_calls` - The tool calls made by the model.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing one tool message per call, in the order of the calls, or an `Error`
+    /// if a function panicked, in which case the other functions are aborted.
+    pub async fn call_all(&self, tool_calls: &[ToolCall]) -> Result<Vec<Message<String>>> {
+        let mut tasks = JoinSet::new();
+        for (position, tool_call) in tool_calls.iter().enumerate() {
+            let future = match self.tools.get(&tool_call.function.name) {
+                Some((_, handler)) => handler(&tool_call.function.arguments),
+                None => {
+                    let name = tool_call.function.name.clone();
+                    Box::pin(async move { Err(Error::UnknownTool(name)) })
+                }
+            };
+            tasks.spawn(async move { (position, future.await) });
+        }
+
+        let mut contents = vec![String::new(); tool_calls.len()];
+        while let Some(result) = tasks.join_next().await {
+            // Returning early drops the set, which aborts the functions still running.
+            let (position, output) = result.map_err(|err| Error::ToolError(err.to_string()))?;
+            contents[position] = match output {
+                Ok(output) => output,
+                Err(err) => format!("Error: {err}"),
+            };
+        }
+        Ok(tool_calls
+            .iter()
+            .zip(contents)
+            .map(|(tool_call, content)| Message::tool(tool_call.id.clone(), content))
+            .collect())
+    }
+}
+
+/// Represents the outcome of `Client::run_with_tools`.
+#[derive(Debug)]
+pub struct ToolRun {
+    /// The final response, which contains no tool calls.
+    pub response: Response,
+    /// The conversation, including the assistant tool calls and tool results exchanged on the way.
+    pub messages: Vec<Message<String>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::FunctionCall;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::time::Duration;
+
+    fn tool_call(id: &str, name: &str) -> ToolCall {
+        ToolCall {
+            id: id.to_string(),
+            r#type: "function".to_string(),
+            function: FunctionCall {
+                name: name.to_string(),
+                arguments: String::new(),
+            },
+        }
+    }
+
+    async fn explode(_: serde_json::Value) -> Result<String> {
+        panic!("tool panicked")
+    }
+
+    #[tokio::test]
+    async fn results_follow_the_order_of_the_calls() {
+        let registry = ToolRegistry::new()
+            .register(
+                "slow",
+                "",
+                serde_json::json!({}),
+                |_: serde_json::Value| async {
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                    Ok("slow")
+                },
+            )
+            .register(
+                "fast",
+                "",
+                serde_json::json!({}),
+                |_: serde_json::Value| async { Ok("fast") },
+            );
+        let calls = [
+            tool_call("a", "slow"),
+            tool_call("b", "fast"),
+            tool_call("c", "missing"),
+        ];
+        let messages = registry.call_all(&calls).await.unwrap();
+        let contents: Vec<&str> = messages.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, ["slow", "fast", "Error: Unknown tool: missing"]);
+        assert_eq!(messages[2].tool_call_id.as_deref(), Some("c"));
+    }
+
+    #[tokio::test]
+    async fn panicking_tool_aborts_the_others() {
+        let finished = Arc::new(AtomicBool::new(false));
+        let flag = finished.clone();
+        let registry = ToolRegistry::new()
+            .register("panic", "", serde_json::json!({}), explode)
+            .register(
+                "slow",
+                "",
+                serde_json::json!({}),
+                move |_: serde_json::Value| {
+                    let flag = flag.clone();
+                    async move {
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                        flag.store(true, Ordering::SeqCst);
+                        Ok(())
+                    }
+                },
+            );
+
+        let calls = [tool_call("a", "slow"), tool_call("b", "panic")];
+        let result = registry.call_all(&calls).await;
+        assert!(matches!(result, Err(Error::ToolError(_))));
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(!finished.load(Ordering::SeqCst));
+    }
+}
@@ -0,0 +1,615 @@
+//! This module defines a local in-memory vector index for retrieval-augmented chat.
+//!
+//! A `VectorIndex` stores documents with their embeddings, computed with `Client::embeddings`,
+//! and searches them by cosine similarity, either exactly by comparing the query with every
+//! document, or approximately with an HNSW graph for larger collections. The index can be saved
+//! to and loaded from a JSON file.
+//!
+//! `VectorIndex::augment` retrieves the passages relevant to a user message and adds them to a
+//! `ChatInput` as a system message, numbered so the model can cite them.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use chatgpt_client::vector_index::{HnswConfig, VectorIndex};
+//! use chatgpt_client::{ChatInput, Client, Message, Model};
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let client = Client::new(std::env::var("OPENAI_API_KEY").unwrap()).unwrap();
+//!
+//!     let mut index = VectorIndex::new(Model::TextEmbedding3Small).hnsw(HnswConfig::default());
+//!     index
+//!         .add_texts(
+//!             &client,
+//!             [
+//!                 ("refunds", "Refunds are processed within 5 business days."),
+//!                 ("shipping", "Orders ship from our warehouse in Lyon."),
+//!             ],
+//!         )
+//!         .await
+//!         .unwrap();
+//!     index.save("index.json").unwrap();
+//!
+//!     let mut input = ChatInput::builder().system("You are a support agent.").build();
+//!     let message = Message::user("How long do refunds take?".to_string());
+//!     let results = index.augment(&client, &mut input, message, 3).await.unwrap();
+//!     for (number, result) in results.iter().enumerate() {
+//!         println!("[{}] {} ({:.2})", number + 1, result.document.id, result.score);
+//!     }
+//!
+//!     let response = client.completion(&input).await.unwrap();
+//!     println!("{}", response.choices[0].message.content);
+//! }
+//! ```
+
+pub mod hnsw;
+
+pub use hnsw::HnswConfig;
+
+use crate::types::{dot, normalize, EmbeddingInput, TokenUsage};
+use crate::{ChatInput, Client, Error, Message, Model, Result};
+use hnsw::Hnsw;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// A document stored in a `VectorIndex`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Document {
+    /// The identifier of the document, used in citations.
+    pub id: String,
+    /// The text of the document.
+    pub text: String,
+    /// The embedding of the text, scaled to unit length.
+    pub vector: Vec<f32>,
+}
+
+/// A document found by a search of a `VectorIndex`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SearchResult<'a> {
+    /// The document found.
+    pub document: &'a Document,
+    /// The cosine similarity of the document with the query, from -1.0 to 1.0.
+    pub score: f32,
+}
+
+/// An in-memory index of documents searched by the similarity of their embeddings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VectorIndex {
+    model: Model,
+    dimensions: Option<u32>,
+    documents: Vec<Document>,
+    hnsw: Option<Hnsw>,
+}
+
+impl VectorIndex {
+    /// The instructions introducing the passages added by `VectorIndex::augment`.
+    pub const CONTEXT_PROMPT: &'static str = "Use the following passages to answer the next \
+        message when they are relevant. Cite the passages you use by their number, such as [1].";
+
+    /// Creates an empty `VectorIndex` searched exactly.
+    ///
+    /// # Arguments
+    ///
+    /// * `model` - The embedding model used for the documents and the queries.
+    ///
+    /// # Returns
+    ///
+    /// A new `VectorIndex` instance.
+    pub fn new(model: impl Into<Model>) -> Self {
+        Self {
+            model: model.into(),
+            dimensions: None,
+            documents: Vec::new(),
+            hnsw: None,
+        }
+    }
+
+    /// Sets the number of dimensions of the embeddings, for models that support shortening.
+    pub fn dimensions(mut self, dimensions: u32) -> Self {
+        self.dimensions = Some(dimensions);
+        self
+    }
+
+    /// Searches the index approximately with an HNSW graph, built over the documents already
+    /// added.
+    pub fn hnsw(mut self, config: HnswConfig) -> Self {
+        let mut hnsw = Hnsw::new(config);
+        for end in 1..=self.documents.len() {
+            hnsw.insert(&self.documents[..end]);
+        }
+        self.hnsw = Some(hnsw);
+        self
+    }
+
+    /// Returns the embedding model used for the documents and the queries.
+    pub fn model(&self) -> &Model {
+        &self.model
+    }
+
+    /// Returns the parameters of the HNSW graph, or `None` if the index is searched exactly.
+    pub fn hnsw_config(&self) -> Option<HnswConfig> {
+        self.hnsw.as_ref().map(Hnsw::config)
+    }
+
+    /// Returns the documents, in the order they were added.
+    pub fn documents(&self) -> &[Document] {
+        &self.documents
+    }
+
+    /// Returns the document with the given identifier.
+    pub fn get(&self, id: &str) -> Option<&Document> {
+        self.documents.iter().find(|document| document.id == id)
+    }
+
+    /// Returns the number of documents.
+    pub fn len(&self) -> usize {
+        self.documents.len()
+    }
+
+    /// Returns `true` if the index has no documents.
+    pub fn is_empty(&self) -> bool {
+        self.documents.is_empty()
+    }
+
+    /// Adds a document whose embedding was already computed.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The identifier of the document, used in citations.
+    /// * `text` - The text of the document.
+    /// * `vector` - The embedding of the text, computed with the model of the index.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is an `Error::DimensionMismatch` if the vector does not have the
+    /// dimensions of the documents already added.
+    pub fn insert(
+        &mut self,
+        id: impl Into<String>,
+        text: impl Into<String>,
+        mut vector: Vec<f32>,
+    ) -> Result<()> {
+        self.check_dimensions(&vector)?;
+        normalize(&mut vector);
+        self.documents.push(Document {
+            id: id.into(),
+            text: text.into(),
+            vector,
+        });
+        if let Some(hnsw) = &mut self.hnsw {
+            hnsw.insert(&self.documents);
+        }
+        Ok(())
+    }
+
+    /// Embeds documents with the model of the index and adds them.
+    ///
+    /// # Arguments
+    ///
+    /// * `client` - The client computing the embeddings.
+    /// * `documents` - The identifiers and texts of the documents.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the token usage of the embeddings, or an `Error`. No document is
+    /// added if the embeddings fail.
+    pub async fn add_texts<I, K, T>(&mut self, client: &Client, documents: I) -> Result<TokenUsage>
+    where
+        I: IntoIterator<Item = (K, T)>,
+        K: Into<String>,
+        T: Into<String>,
+    {
+        let (ids, texts): (Vec<String>, Vec<String>) = documents
+            .into_iter()
+            .map(|(id, text)| (id.into(), text.into()))
+            .unzip();
+        if texts.is_empty() {
+            return Ok(TokenUsage::default());
+        }
+
+        let response = client.embeddings(&self.embedding_input(&texts)).await?;
+        if response.data.len() != texts.len() {
+            return Err(Error::ResponseError(format!(
+                "Expected {} embeddings, received {}",
+                texts.len(),
+                response.data.len()
+            )));
+        }
+        let documents = ids
+            .into_iter()
+            .zip(texts)
+            .zip(response.data)
+            .map(|((id, text), embedding)| (id, text, embedding.embedding))
+            .collect();
+        self.insert_all(documents)?;
+        Ok(response.usage)
+    }
+
+    /// Adds documents whose embeddings were already computed, either all of them or none if
+    /// any vector does not have the dimensions of the others.
+    fn insert_all(&mut self, documents: Vec<(String, String, Vec<f32>)>) -> Result<()> {
+        let Some((_, _, first)) = documents.first() else {
+            return Ok(());
+        };
+        self.check_dimensions(first)?;
+        let expected = first.len();
+        if let Some((_, _, vector)) = documents.iter().find(|(_, _, v)| v.len() != expected) {
+            return Err(Error::DimensionMismatch {
+                expected,
+                actual: vector.len(),
+            });
+        }
+        for (id, text, vector) in documents {
+            self.insert(id, text, vector)?;
+        }
+        Ok(())
+    }
+
+    /// Searches the documents most similar to a query embedding, with the HNSW graph if the
+    /// index has one.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The embedding of the query, computed with the model of the index.
+    /// * `k` - The maximum number of results.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing up to `k` results, most similar first, or an
+    /// `Error::DimensionMismatch` if the query does not have the dimensions of the documents.
+    pub fn search(&self, query: &[f32], k: usize) -> Result<Vec<SearchResult<'_>>> {
+        let Some(hnsw) = &self.hnsw else {
+            return self.search_exact(query, k);
+        };
+        self.check_dimensions(query)?;
+        let mut query = query.to_vec();
+        normalize(&mut query);
+        Ok(hnsw
+            .search(&query, k, &self.documents)
+            .into_iter()
+            .map(|(position, score)| SearchResult {
+                document: &self.documents[position],
+                score,
+            })
+            .collect())
+    }
+
+    /// Searches the documents most similar to a query embedding by comparing it with every
+    /// document.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The embedding of the query, computed with the model of the index.
+    /// * `k` - The maximum number of results.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing up to `k` results, most similar first, or an
+    /// `Error::DimensionMismatch` if the query does not have the dimensions of the documents.
+    pub fn search_exact(&self, query: &[f32], k: usize) -> Result<Vec<SearchResult<'_>>> {
+        self.check_dimensions(query)?;
+        let mut query = query.to_vec();
+        normalize(&mut query);
+        let mut results: Vec<SearchResult> = self
+            .documents
+            .iter()
+            .map(|document| SearchResult {
+                document,
+                score: dot(&query, &document.vector),
+            })
+            .collect();
+        results.sort_by(|a, b| b.score.total_cmp(&a.score));
+        results.truncate(k);
+        Ok(results)
+    }
+
+    /// Embeds a query with the model of the index and searches the most similar documents.
+    ///
+    /// # Arguments
+    ///
+    /// * `client` - The client computing the embedding.
+    /// * `query` - The text of the query.
+    /// * `k` - The maximum number of results.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing up to `k` results, most similar first, or an `Error`.
+    pub async fn search_text(
+        &self,
+        client: &Client,
+        query: &str,
+        k: usize,
+    ) -> Result<Vec<SearchResult<'_>>> {
+        if self.documents.is_empty() || k == 0 {
+            return Ok(Vec::new());
+        }
+        let response = client
+            .embeddings(&self.embedding_input(&[query.to_string()]))
+            .await?;
+        let embedding =
+            response.data.into_iter().next().ok_or_else(|| {
+                Error::ResponseError("There is no embedding in response".to_string())
+            })?;
+        self.search(&embedding.embedding, k)
+    }
+
+    /// Retrieves the passages relevant to a user message and adds them to a `ChatInput`,
+    /// followed by the message.
+    ///
+    /// The passages are added as a system message starting with `VectorIndex::CONTEXT_PROMPT`,
+    /// each numbered and labelled with the identifier of its document, such as
+    /// `[1] (refunds) Refunds are processed within 5 business days.` No system message is added
+    /// if no passage is found.
+    ///
+    /// # Arguments
+    ///
+    /// * `client` - The client computing the embedding of the message.
+    /// * `input` - The `ChatInput` receiving the passages and the message.
+    /// * `message` - The user message, whose content is the query.
+    /// * `k` - The maximum number of passages.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the passages in the order of their citation numbers, starting at
+    /// 1, or an `Error`. The input is left unchanged on error.
+    pub async fn augment(
+        &self,
+        client: &Client,
+        input: &mut ChatInput,
+        message: Message<String>,
+        k: usize,
+    ) -> Result<Vec<SearchResult<'_>>> {
+        let results = self.search_text(client, &message.content, k).await?;
+        if !results.is_empty() {
+            let passages: Vec<String> = results
+                .iter()
+                .enumerate()
+                .map(|(number, result)| {
+                    format!(
+                        "[{}] ({}) {}",
+                        number + 1,
+                        result.document.id,
+                        result.document.text
+                    )
+                })
+                .collect();
+            let context = format!("{}\n\n{}", Self::CONTEXT_PROMPT, passages.join("\n\n"));
+            input.messages.push(Message::system(context));
+        }
+        input.messages.push(message);
+        Ok(results)
+    }
+
+    /// Saves the index, including its HNSW graph, to a JSON file.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file, replaced if it exists.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is an `Error` if the file cannot be written.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let mut temporary = path.as_os_str().to_owned();
+        temporary.push(".tmp");
+        fs::write(&temporary, serde_json::to_vec(self)?)?;
+        fs::rename(&temporary, path)?;
+        Ok(())
+    }
+
+    /// Loads an index saved with `VectorIndex::save`.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the loaded `VectorIndex`, or an `Error` if the file cannot be read
+    /// or parsed, or if its documents and graph are inconsistent.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let index: Self = serde_json::from_slice(&fs::read(path)?)?;
+        index.validate()?;
+        Ok(index)
+    }
+
+    /// Checks that the documents of a loaded index have the same dimensions and that its HNSW
+    /// graph links them, as searches index the documents by the nodes of the graph.
+    fn validate(&self) -> Result<()> {
+        for document in &self.documents {
+            self.check_dimensions(&document.vector)?;
+        }
+        match &self.hnsw {
+            Some(hnsw) => hnsw.validate(self.documents.len()),
+            None => Ok(()),
+        }
+    }
+
+    /// Builds the embeddings request for the given texts.
+    fn embedding_input(&self, texts: &[String]) -> EmbeddingInput {
+        let input = EmbeddingInput::new(self.model.clone(), texts.iter().cloned());
+        match self.dimensions {
+            Some(dimensions) => input.dimensions(dimensions),
+            None => input,
+        }
+    }
+
+    /// Checks that a vector has the dimensions of the documents already added.
+    fn check_dimensions(&self, vector: &[f32]) -> Result<()> {
+        match self.documents.first() {
+            Some(document) if document.vector.len() != vector.len() => {
+                Err(Error::DimensionMismatch {
+                    expected: document.vector.len(),
+                    actual: vector.len(),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn random_vectors(rng: &mut fastrand::Rng, count: usize, dimensions: usize) -> Vec<Vec<f32>> {
+        (0..count)
+            .map(|_| {
+                let mut vector: Vec<f32> = (0..dimensions).map(|_| rng.f32() * 2.0 - 1.0).collect();
+                normalize(&mut vector);
+                vector
+            })
+            .collect()
+    }
+
+    fn index(config: Option<HnswConfig>, vectors: &[Vec<f32>]) -> VectorIndex {
+        let mut index = VectorIndex::new(Model::TextEmbedding3Small);
+        if let Some(config) = config {
+            index = index.hnsw(config);
+        }
+        for (position, vector) in vectors.iter().enumerate() {
+            index
+                .insert(position.to_string(), "", vector.clone())
+                .unwrap();
+        }
+        index
+    }
+
+    fn ids(results: Vec<SearchResult<'_>>) -> Vec<&str> {
+        results
+            .into_iter()
+            .map(|result| result.document.id.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn hnsw_recall_matches_exact_search() {
+        let mut rng = fastrand::Rng::with_seed(7);
+        let vectors = random_vectors(&mut rng, 400, 16);
+        let index = index(Some(HnswConfig::default()), &vectors);
+
+        let queries = random_vectors(&mut rng, 50, 16);
+        let mut found = 0;
+        for query in &queries {
+            let exact = ids(index.search_exact(query, 10).unwrap());
+            let approximate = ids(index.search(query, 10).unwrap());
+            found += approximate.iter().filter(|id| exact.contains(id)).count();
+        }
+        let recall = found as f64 / (queries.len() * 10) as f64;
+        assert!(recall >= 0.9, "recall {recall}");
+    }
+
+    #[test]
+    fn same_seed_builds_same_graph() {
+        let vectors = random_vectors(&mut fastrand::Rng::with_seed(1), 100, 8);
+        let config = HnswConfig {
+            seed: 42,
+            ..HnswConfig::default()
+        };
+        let first = serde_json::to_string(&index(Some(config), &vectors)).unwrap();
+        let second = serde_json::to_string(&index(Some(config), &vectors)).unwrap();
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn save_and_load_give_same_results() {
+        let mut rng = fastrand::Rng::with_seed(3);
+        let vectors = random_vectors(&mut rng, 200, 8);
+        let index = index(Some(HnswConfig::default()), &vectors);
+        let path = std::env::temp_dir().join(format!("vector-index-{}.json", fastrand::u64(..)));
+        index.save(&path).unwrap();
+        let loaded = VectorIndex::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.documents(), index.documents());
+        assert_eq!(loaded.hnsw_config(), index.hnsw_config());
+        for query in random_vectors(&mut rng, 20, 8) {
+            assert_eq!(
+                loaded.search(&query, 5).unwrap(),
+                index.search(&query, 5).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn load_rejects_inconsistent_graph() {
+        let vectors = random_vectors(&mut fastrand::Rng::with_seed(5), 20, 4);
+        let saved = serde_json::to_value(index(Some(HnswConfig::default()), &vectors)).unwrap();
+        let path = std::env::temp_dir().join(format!("vector-index-{}.json", fastrand::u64(..)));
+        let load = |value: &serde_json::Value| {
+            fs::write(&path, serde_json::to_vec(value).unwrap()).unwrap();
+            VectorIndex::load(&path)
+        };
+
+        let mut missing_node = saved.clone();
+        missing_node["hnsw"]["neighbors"][0][0] = serde_json::json!([20]);
+        assert!(matches!(
+            load(&missing_node),
+            Err(Error::InvalidVectorIndex(_))
+        ));
+
+        let mut truncated = saved.clone();
+        truncated["hnsw"]["neighbors"].as_array_mut().unwrap().pop();
+        assert!(matches!(
+            load(&truncated),
+            Err(Error::InvalidVectorIndex(_))
+        ));
+
+        let mut entry_point = saved.clone();
+        entry_point["hnsw"]["entry_point"] = serde_json::json!(20);
+        assert!(matches!(
+            load(&entry_point),
+            Err(Error::InvalidVectorIndex(_))
+        ));
+
+        let mut dimensions = saved.clone();
+        dimensions["documents"][3]["vector"] = serde_json::json!([1.0, 0.0]);
+        assert!(matches!(
+            load(&dimensions),
+            Err(Error::DimensionMismatch {
+                expected: 4,
+                actual: 2
+            })
+        ));
+
+        assert!(load(&saved).is_ok());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn search_rejects_wrong_dimensions() {
+        let vectors = random_vectors(&mut fastrand::Rng::with_seed(9), 10, 4);
+        for config in [None, Some(HnswConfig::default())] {
+            let index = index(config, &vectors);
+            assert!(matches!(
+                index.search(&[1.0, 0.0], 3),
+                Err(Error::DimensionMismatch {
+                    expected: 4,
+                    actual: 2
+                })
+            ));
+        }
+    }
+
+    #[test]
+    fn insert_all_adds_nothing_on_mismatch() {
+        let mut index = index(None, &[vec![1.0, 0.0, 0.0]]);
+        let documents = vec![
+            ("a".to_string(), "A".to_string(), vec![0.0, 1.0, 0.0]),
+            ("b".to_string(), "B".to_string(), vec![0.0, 1.0]),
+        ];
+        assert!(index.insert_all(documents).is_err());
+        assert_eq!(index.len(), 1);
+
+        let mut empty = VectorIndex::new(Model::TextEmbedding3Small);
+        let documents = vec![
+            ("a".to_string(), "A".to_string(), vec![0.0, 1.0]),
+            ("b".to_string(), "B".to_string(), vec![0.0, 1.0, 0.0]),
+        ];
+        assert!(empty.insert_all(documents).is_err());
+        assert!(empty.is_empty());
+    }
+}
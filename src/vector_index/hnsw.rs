@@ -0,0 +1,289 @@
+use crate::types::dot;
+use crate::vector_index::Document;
+use crate::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashSet};
+
+/// The parameters of the HNSW (hierarchical navigable small world) graph of a `VectorIndex`.
+///
+/// Larger values improve the recall of searches at the cost of memory and speed.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HnswConfig {
+    /// The number of neighbors of a document on each layer, twice as many on the bottom layer.
+    pub m: usize,
+    /// The number of candidates considered when inserting a document.
+    pub ef_construction: usize,
+    /// The number of candidates considered when searching, at least the number of results.
+    pub ef_search: usize,
+    /// The seed drawing the layers of the documents, so that the same documents always build
+    /// the same graph.
+    pub seed: u64,
+}
+
+impl Default for HnswConfig {
+    /// Provides default values for `HnswConfig`.
+    ///
+    /// # Returns
+    ///
+    /// An `HnswConfig` with 16 neighbors, 100 candidates on insertion, 50 on search and a seed
+    /// of 0.
+    fn default() -> Self {
+        Self {
+            m: 16,
+            ef_construction: 100,
+            ef_search: 50,
+            seed: 0,
+        }
+    }
+}
+
+/// A document ranked by its similarity to a query.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Scored {
+    score: f32,
+    node: usize,
+}
+
+impl Eq for Scored {}
+
+impl Ord for Scored {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.score
+            .total_cmp(&other.score)
+            .then_with(|| other.node.cmp(&self.node))
+    }
+}
+
+impl PartialOrd for Scored {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// An HNSW graph over the unit vectors of the documents of a `VectorIndex`, whose nodes are the
+/// positions of the documents.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct Hnsw {
+    config: HnswConfig,
+    /// The neighbors of every node on each of the layers it belongs to, bottom layer first.
+    neighbors: Vec<Vec<Vec<usize>>>,
+    entry_point: Option<usize>,
+    /// The state of the random number generator drawing the layers of new nodes.
+    rng_state: u64,
+}
+
+impl Hnsw {
+    /// Creates an empty graph.
+    pub(crate) fn new(config: HnswConfig) -> Self {
+        Self {
+            config,
+            neighbors: Vec::new(),
+            entry_point: None,
+            rng_state: config.seed,
+        }
+    }
+
+    /// Returns the parameters of the graph.
+    pub(crate) fn config(&self) -> HnswConfig {
+        self.config
+    }
+
+    /// Draws the top layer of a new node, with exponentially fewer nodes on each layer.
+    fn random_level(&mut self) -> usize {
+        let mut rng = fastrand::Rng::with_seed(self.rng_state);
+        let uniform = rng.f64();
+        self.rng_state = rng.get_seed();
+
+        let scale = 1.0 / (self.config.m.max(2) as f64).ln();
+        let level = -uniform.max(f64::MIN_POSITIVE).ln() * scale;
+        (level as usize).min(16)
+    }
+
+    /// Checks that the graph links the given number of documents, so that searches never
+    /// reach a missing document or layer.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is an `Error::InvalidVectorIndex` describing the first inconsistency.
+    pub(crate) fn validate(&self, documents: usize) -> Result<()> {
+        let invalid = |reason: String| Err(Error::InvalidVectorIndex(reason));
+        if self.neighbors.len() != documents {
+            return invalid(format!(
+                "The graph has {} nodes for {documents} documents",
+                self.neighbors.len()
+            ));
+        }
+        match self.entry_point {
+            None if documents > 0 => return invalid("The graph has no entry point".to_string()),
+            Some(entry_point) if entry_point >= documents => {
+                return invalid(format!("The entry point {entry_point} is not a node"));
+            }
+            _ => {}
+        }
+        for (node, layers) in self.neighbors.iter().enumerate() {
+            if layers.is_empty() {
+                return invalid(format!("The node {node} is on no layer"));
+            }
+            for (layer, neighbors) in layers.iter().enumerate() {
+                let missing = neighbors.iter().find(|&&neighbor| {
+                    neighbor >= documents || self.neighbors[neighbor].len() <= layer
+                });
+                if let Some(neighbor) = missing {
+                    return invalid(format!(
+                        "The node {node} links to {neighbor}, which is not a node of layer {layer}"
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Inserts the next document, which must be the one following the last inserted document.
+    pub(crate) fn insert(&mut self, documents: &[Document]) {
+        let node = self.neighbors.len();
+        let level = self.random_level();
+        self.neighbors.push(vec![Vec::new(); level + 1]);
+
+        let Some(entry_point) = self.entry_point else {
+            self.entry_point = Some(node);
+            return;
+        };
+        let top_level = self.neighbors[entry_point].len() - 1;
+        let query = &documents[node].vector;
+
+        let mut entries = vec![entry_point];
+        for layer in (level + 1..=top_level).rev() {
+            entries = self.greedy(query, entries, layer, documents);
+        }
+        for layer in (0..=level.min(top_level)).rev() {
+            let candidates = self.search_layer(
+                query,
+                &entries,
+                self.config.ef_construction.max(1),
+                layer,
+                documents,
+            );
+            let max_neighbors = if layer == 0 {
+                self.config.m * 2
+            } else {
+                self.config.m
+            };
+            let neighbors: Vec<usize> = candidates
+                .iter()
+                .take(self.config.m.max(1))
+                .map(|scored| scored.node)
+                .collect();
+            for &neighbor in &neighbors {
+                let links = &mut self.neighbors[neighbor][layer];
+                links.push(node);
+                if links.len() > max_neighbors {
+                    // Keep the closest neighbors of the neighbor.
+                    let vector = &documents[neighbor].vector;
+                    links.sort_by(|a, b| {
+                        let a = dot(vector, &documents[*a].vector);
+                        let b = dot(vector, &documents[*b].vector);
+                        b.total_cmp(&a)
+                    });
+                    links.truncate(max_neighbors);
+                }
+            }
+            self.neighbors[node][layer] = neighbors;
+            entries = candidates.into_iter().map(|scored| scored.node).collect();
+        }
+
+        if level > top_level {
+            self.entry_point = Some(node);
+        }
+    }
+
+    /// Searches the nodes most similar to a unit vector.
+    ///
+    /// # Returns
+    ///
+    /// Up to `k` nodes with their similarity, most similar first.
+    pub(crate) fn search(
+        &self,
+        query: &[f32],
+        k: usize,
+        documents: &[Document],
+    ) -> Vec<(usize, f32)> {
+        let Some(entry_point) = self.entry_point else {
+            return Vec::new();
+        };
+        let mut entries = vec![entry_point];
+        for layer in (1..self.neighbors[entry_point].len()).rev() {
+            entries = self.greedy(query, entries, layer, documents);
+        }
+        let ef = self.config.ef_search.max(k);
+        self.search_layer(query, &entries, ef, 0, documents)
+            .into_iter()
+            .take(k)
+            .map(|scored| (scored.node, scored.score))
+            .collect()
+    }
+
+    /// Finds the node most similar to the query on an upper layer.
+    fn greedy(
+        &self,
+        query: &[f32],
+        entries: Vec<usize>,
+        layer: usize,
+        documents: &[Document],
+    ) -> Vec<usize> {
+        self.search_layer(query, &entries, 1, layer, documents)
+            .first()
+            .map_or(entries, |scored| vec![scored.node])
+    }
+
+    /// Searches the `ef` nodes most similar to the query on a layer, starting from the entries.
+    ///
+    /// # Returns
+    ///
+    /// The nodes found, most similar first.
+    fn search_layer(
+        &self,
+        query: &[f32],
+        entries: &[usize],
+        ef: usize,
+        layer: usize,
+        documents: &[Document],
+    ) -> Vec<Scored> {
+        let score = |node: usize| Scored {
+            score: dot(query, &documents[node].vector),
+            node,
+        };
+        let mut visited: HashSet<usize> = entries.iter().copied().collect();
+        let mut candidates: BinaryHeap<Scored> = entries.iter().map(|&node| score(node)).collect();
+        let mut found: BinaryHeap<Reverse<Scored>> =
+            candidates.iter().copied().map(Reverse).collect();
+        while found.len() > ef {
+            found.pop();
+        }
+
+        while let Some(candidate) = candidates.pop() {
+            let worst = found.peek().map(|Reverse(worst)| *worst);
+            if found.len() >= ef && worst.is_some_and(|worst| candidate < worst) {
+                break;
+            }
+            for &neighbor in &self.neighbors[candidate.node][layer] {
+                if !visited.insert(neighbor) {
+                    continue;
+                }
+                let neighbor = score(neighbor);
+                let worst = found.peek().map(|Reverse(worst)| *worst);
+                if found.len() < ef || worst.is_some_and(|worst| neighbor > worst) {
+                    candidates.push(neighbor);
+                    found.push(Reverse(neighbor));
+                    if found.len() > ef {
+                        found.pop();
+                    }
+                }
+            }
+        }
+
+        let mut found: Vec<Scored> = found.into_iter().map(|Reverse(scored)| scored).collect();
+        found.sort_by(|a, b| b.cmp(a));
+        found
+    }
+}